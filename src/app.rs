@@ -1,23 +1,150 @@
 use crate::{
-    board::{Board, Move, Position},
-    console::{Command, Console, ParsedMove, CMD_PREFIX},
+    board::{Board, Coordinates, Drag, Move, Overlays, Position, SquareSize},
+    console::{parse_move_values, Command, Console, EngineCommand, ParsedMove, CMD_PREFIX},
+    engine::{EngineError, EngineManager, SearchInfo, SearchLimit, SearchParams},
+    game::{AddedLine, GameAnalysis, GameRecord, Judgement, PlyEval},
     help::HelpWindow,
+    options::EngineOptions,
+    pv::{PvError, PvExplorer, PvLine},
+    theme::{piece_set_names, theme_names, ColorSupport, Palette, PieceSet, Theme, THEMES},
     tree::StatefulTree,
 };
 use anyhow::Result;
-use async_trait::async_trait;
-use async_uci::engine::{ChessEngine, EngineOption, Evaluation};
+use async_uci::{engine::Evaluation, parse::OptionType};
 use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use tui::layout::Rect;
 use tui_textarea::CursorMove;
 use tui_tree_widget::TreeItem;
 
 pub const INITIAL_POSITION: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq";
 
+/// Consecutive automatic restarts tried before giving up on a failing engine.
+const MAX_ENGINE_RESTARTS: usize = 3;
+
+/// Option of the engines to enable reporting win/draw/loss probabilities.
+const SHOW_WDL_OPTION: &str = "UCI_ShowWDL";
+
+/// Depth searched for each position by `:analyze` when no limit is given.
+const ANALYSIS_DEPTH: usize = 14;
+
+/// Depth of the null move search used to find the threat of the opponent.
+const THREAT_DEPTH: usize = 12;
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum SecondaryBoardPane {
     None,
     MovesTree,
     History,
+    PvExplorer,
+    EvalGraph,
+}
+
+/// Null move search of the selected engine: the side to move passes and the engine searches
+/// the position, its best move is what the opponent threatens to play.
+pub struct ThreatSearch {
+    /// Position the threat is searched for.
+    pub fen: String,
+    /// Whether the engine is still searching the null move position.
+    pub pending: bool,
+}
+
+/// Move typed on the Board tab, with the legal moves it may still become.
+#[derive(Debug, Default)]
+pub struct MoveInput {
+    pub text: String,
+    /// Legal moves matching the text, with their SAN.
+    pub candidates: Vec<(Move, String)>,
+}
+
+impl MoveInput {
+    /// The move Enter plays: the only candidate left, or the one the text spells out in full.
+    pub fn chosen(&self) -> Option<Move> {
+        if let [(mov, _)] = self.candidates.as_slice() {
+            return Some(*mov);
+        }
+        let text = normalize_move(&self.text);
+        let uci = self.text.to_ascii_lowercase();
+        self.candidates
+            .iter()
+            .find(|(mov, san)| normalize_move(san) == text || mov.as_uci() == uci)
+            .map(|(mov, _)| *mov)
+    }
+}
+
+/// Move text without capture, check and promotion marks, so `Nxe5+`, `ne5` and `0-0`,
+/// `oo` compare equal. Piece letters are uppercased and files lowercased, except for `b`
+/// and `B` which tell a pawn on the b-file from a bishop, i.e. `bxc3` from `Bxc3`.
+fn normalize_move(text: &str) -> String {
+    text.chars()
+        .filter(|c| !"x+#=-!?".contains(*c))
+        .map(|c| match c {
+            '0' | 'O' => 'o',
+            'k' | 'q' | 'r' | 'n' => c.to_ascii_uppercase(),
+            'K' | 'Q' | 'R' | 'N' | 'B' => c,
+            c => c.to_ascii_lowercase(),
+        })
+        .collect()
+}
+
+/// Areas of the widgets that handle clicks, as drawn in the last frame. They are recorded
+/// by `ui::draw`, as the layout depends on the size of the terminal and the panes open.
+#[derive(Debug, Default)]
+pub struct HitAreas {
+    /// Title of each tab, in the order of the tabs.
+    pub tabs: Vec<Rect>,
+    /// Board, borders included.
+    pub board: Option<Rect>,
+    /// Rows of the move history tree.
+    pub history: Option<Rect>,
+    /// Rows of the legal moves tree.
+    pub moves_tree: Option<Rect>,
+    /// Plotting area of the evaluation graph.
+    pub eval_graph: Option<Rect>,
+}
+
+/// How the moves of the legal moves pane are grouped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveGrouping {
+    /// By the piece moved.
+    Piece,
+    /// Checks, then the other captures, then quiet moves.
+    Kind,
+    Off,
+}
+
+impl MoveGrouping {
+    pub fn names(&self) -> &'static [&'static str] {
+        match self {
+            MoveGrouping::Piece => &["King", "Queen", "Rook", "Bishop", "Knight", "Pawn"],
+            MoveGrouping::Kind => &["Checks", "Captures", "Quiet"],
+            MoveGrouping::Off => &["Moves"],
+        }
+    }
+
+    /// Index in `names` of the group of a move, from its SAN.
+    fn group_of(&self, san: &str) -> usize {
+        match self {
+            MoveGrouping::Piece => match san.chars().next() {
+                Some('K' | 'O') => 0,
+                Some('Q') => 1,
+                Some('R') => 2,
+                Some('B') => 3,
+                Some('N') => 4,
+                _ => 5,
+            },
+            MoveGrouping::Kind if san.ends_with(['+', '#']) => 0,
+            MoveGrouping::Kind if san.contains('x') => 1,
+            MoveGrouping::Kind => 2,
+            MoveGrouping::Off => 0,
+        }
+    }
+}
+
+/// Engine moves highlighted on the board.
+pub enum BestMoveHighlight {
+    Off,
+    BestMove,
+    WithReply,
 }
 
 pub struct App<'a> {
@@ -30,94 +157,480 @@ pub struct App<'a> {
     pub console: Console,
     pub in_console_input: bool,
 
-    pub engine: &'a mut dyn ChessEngine,
-    pub last_engine_eval: Evaluation,
+    pub engine: EngineManager,
     pub searching: bool,
+    pub search_params: SearchParams,
+    pub engine_autorestart: bool,
+    pub log_evaluations: bool,
+    pub status_error: Option<String>,
 
     pub piece_to_grab: Option<Position>,
+    pub drag: Option<Drag>,
+    /// Square of the keyboard cursor on the board, hidden until an arrow key is pressed.
+    pub board_cursor: Option<Position>,
+    pub move_input: Option<MoveInput>,
     pub help: HelpWindow,
 
     pub tabs: TabsState<'a>,
     pub moves_tree: StatefulTree<'a>,
+    /// Moves of each group of the legal moves tree, a single group when they aren't grouped.
+    pub moves_tree_moves: Vec<Vec<Move>>,
+    pub move_grouping: MoveGrouping,
     pub history_tree: StatefulTree<'a>,
 
     pub secondary_pane: SecondaryBoardPane,
+    pub best_move_highlight: BestMoveHighlight,
+    pub pv_explorer: Option<PvExplorer>,
+    pub game: GameRecord,
+    pub analysis: Option<GameAnalysis>,
+    pub overlays: Overlays,
+    pub coordinates: Coordinates,
+    pub threat_search: Option<ThreatSearch>,
+    pub hit_areas: HitAreas,
+    pub theme: &'static Theme,
+    pub color_support: ColorSupport,
+    pub piece_set: PieceSet,
 }
 
 /// Functional Implementations
 impl<'a> App<'a> {
-    pub fn new(engine: &'a mut dyn ChessEngine) -> Result<App<'a>> {
-        App::from_fen(engine, INITIAL_POSITION.to_string())
+    pub fn new() -> Result<App<'a>> {
+        App::from_fen(INITIAL_POSITION.to_string())
     }
 
-    pub fn from_fen(engine: &'a mut dyn ChessEngine, fen: String) -> Result<App<'a>> {
+    pub fn from_fen(fen: String) -> Result<App<'a>> {
         let mut app = App {
             title: "Chess TUI".to_string(),
             should_quit: false,
-            tabs: TabsState::new(vec!["Board", "Console", "Help"]),
-            board: Board::from_fen(fen)?,
+            tabs: TabsState::new(vec!["Board", "Console", "Engine", "Help"]),
+            board: Board::from_fen(fen.clone())?,
             flipped_board: false,
             console: Console::new(),
             in_console_input: false,
-            engine,
-            last_engine_eval: Evaluation::default(),
+            engine: EngineManager::new(),
             piece_to_grab: None,
+            drag: None,
+            board_cursor: None,
+            move_input: None,
             searching: false,
+            search_params: SearchParams::default(),
+            engine_autorestart: false,
+            log_evaluations: false,
+            status_error: None,
             moves_tree: StatefulTree::with_items(Vec::new()),
+            moves_tree_moves: Vec::new(),
+            move_grouping: MoveGrouping::Piece,
             history_tree: StatefulTree::with_items(Vec::new()),
             help: HelpWindow::new(),
             secondary_pane: SecondaryBoardPane::None,
+            best_move_highlight: BestMoveHighlight::BestMove,
+            pv_explorer: None,
+            game: GameRecord::new(fen),
+            analysis: None,
+            overlays: Overlays::default(),
+            coordinates: Coordinates::Outside,
+            threat_search: None,
+            hit_areas: HitAreas::default(),
+            theme: &THEMES[0],
+            color_support: ColorSupport::detect(),
+            piece_set: PieceSet::Unicode,
         };
+        app.board.set_palette(app.palette());
         app.update_trees();
         Ok(app)
     }
 
+    /// Load the options advertised by the engine into the Engine tab.
+    async fn load_engine_options(&mut self, ix: usize) {
+        let slot = &mut self.engine.slots[ix];
+        match slot.engine.get_options().await {
+            Ok(options) => slot.options.set_options(options),
+            Err(err) => self
+                .console
+                .log_line(format!("err: could not get engine options: {}", err)),
+        }
+    }
+
+    /// Set an option in the selected engine.
+    async fn set_engine_option(&mut self, name: String, value: String) {
+        let slot = self.engine.current_mut();
+        let (name, value) = match slot.options.validate(&name, &value) {
+            Ok(v) => v,
+            Err(err) => {
+                self.console.log_line(format!("err: {}", err));
+                return;
+            }
+        };
+        match slot.engine.set_option(name.clone(), value.clone()).await {
+            Ok(_) => {
+                slot.options.set_value(&name, value.clone());
+                self.console
+                    .log_line(format!("option {} set to '{}'", name, value));
+            }
+            Err(err) => self.console.log_line(format!("err: {}", err)),
+        }
+    }
+
+    /// Step the selected option in the Engine tab, see `OptionEntry::step`.
+    async fn step_selected_option(&mut self, forwards: bool) {
+        let (name, value) = match self.engine.current().options.selected() {
+            Some(entry) => match entry.step(forwards) {
+                Some(value) => (entry.option.name.clone(), value),
+                None => return,
+            },
+            None => return,
+        };
+        self.set_engine_option(name, value).await;
+    }
+
+    async fn activate_selected_option(&mut self) {
+        let options = &mut self.engine.current_mut().options;
+        if options.is_editing() {
+            match options.finish_editing() {
+                Some(Ok((name, value))) => self.set_engine_option(name, value).await,
+                Some(Err(err)) => self.console.log_line(format!("err: {}", err)),
+                None => {}
+            }
+            return;
+        }
+        let (name, opt_type) = match options.selected() {
+            Some(entry) => (entry.option.name.clone(), entry.option.opt_type.clone()),
+            None => return,
+        };
+        match opt_type {
+            OptionType::Button => self.set_engine_option(name, String::new()).await,
+            OptionType::Spin { .. } | OptionType::String { .. } => options.start_editing(),
+            _ => self.step_selected_option(true).await,
+        }
+    }
+
+    /// Start the engine at `path` in the given slot, replacing the engine in it.
+    pub async fn load_engine(&mut self, ix: usize, path: String) -> Result<()> {
+        let slot = self.engine.get_mut(ix)?;
+        slot.options = EngineOptions::new();
+        let res = slot.load(path).await;
+        self.load_engine_options(ix).await;
+        if res.is_ok() {
+            self.enable_wdl(ix).await;
+        }
+        self.on_engine_changed(ix).await;
+        res
+    }
+
+    /// Ask the engine to report win/draw/loss probabilities, if it supports it.
+    async fn enable_wdl(&mut self, ix: usize) {
+        let slot = &mut self.engine.slots[ix];
+        if slot.options.get(SHOW_WDL_OPTION).is_none() {
+            return;
+        }
+        match slot
+            .engine
+            .set_option(SHOW_WDL_OPTION.to_string(), "true".to_string())
+            .await
+        {
+            Ok(_) => slot.options.set_value(SHOW_WDL_OPTION, "true".to_string()),
+            Err(err) => self.console.log_line(format!("err: {}", err)),
+        }
+    }
+
+    /// Start the engine at `path` alongside the ones already attached.
+    pub async fn add_engine(&mut self, path: String) -> Result<()> {
+        let ix = self.engine.add();
+        self.load_engine(ix, path).await
+    }
+
+    async fn restart_engine(&mut self, ix: usize) -> Result<()> {
+        let res = self.engine.get_mut(ix)?.restart().await;
+        self.load_engine_options(ix).await;
+        if res.is_ok() {
+            self.reapply_engine_options(ix).await;
+        }
+        self.on_engine_changed(ix).await;
+        res
+    }
+
+    async fn unload_engine(&mut self, ix: usize) -> Result<()> {
+        self.engine.remove(ix).await
+    }
+
+    /// Sync a freshly started engine with the current state of the app, including the
+    /// search if one was running. Its options must be set before, to apply to the search.
+    async fn on_engine_changed(&mut self, ix: usize) {
+        if let Err(err) = self.update_engine_position(ix).await {
+            self.report_error(format!("engine {}: {}", ix + 1, err));
+        }
+    }
+
+    /// Handle an engine that crashed or stopped responding. The engine is restarted if
+    /// `engine_autorestart` is set, otherwise it's replaced by a `NoopEngine`.
+    async fn on_engine_failure(&mut self, ix: usize, err: anyhow::Error) {
+        self.report_error(format!("engine {}: {}", ix + 1, err));
+        let slot = &mut self.engine.slots[ix];
+        if self.engine_autorestart && slot.restarts < MAX_ENGINE_RESTARTS {
+            slot.restarts += 1;
+            self.console.log_line(format!(
+                "restarting engine {} (attempt {}/{})",
+                ix + 1,
+                slot.restarts,
+                MAX_ENGINE_RESTARTS
+            ));
+            match self.restart_engine(ix).await {
+                Ok(_) => self.console.log_line("engine restarted".to_string()),
+                Err(err) => {
+                    self.report_error(format!("engine {}: restart failed: {}", ix + 1, err))
+                }
+            }
+            return;
+        }
+        if let Err(err) = slot.shutdown().await {
+            self.console.log_line(format!("err: {}", err));
+        }
+        self.console.log_line(format!(
+            "engine {} stopped, use `:engine restart {}` to start it again",
+            ix + 1,
+            ix + 1
+        ));
+    }
+
+    /// Check the engines are alive, handling failures as configured.
+    async fn check_engine_health(&mut self) {
+        for ix in 0..self.engine.slots.len() {
+            let slot = &mut self.engine.slots[ix];
+            if !slot.is_running() {
+                continue;
+            }
+            if let Err(err) = slot.check_health().await {
+                self.on_engine_failure(ix, err).await;
+            }
+        }
+    }
+
+    /// Log an error in the console and show it in the status bar.
+    pub fn report_error(&mut self, msg: String) {
+        self.console.log_line(format!("err: {}", msg));
+        self.status_error = Some(msg);
+    }
+
+    /// Send the options changed by the user to the engine again, used after restarts.
+    async fn reapply_engine_options(&mut self, ix: usize) {
+        let slot = &mut self.engine.slots[ix];
+        let changed = slot
+            .options
+            .entries
+            .iter()
+            .filter(|e| e.option.opt_type != OptionType::Button && e.value != e.default())
+            .map(|e| (e.option.name.clone(), e.value.clone()))
+            .collect::<Vec<_>>();
+        for (name, value) in changed {
+            if let Err(err) = slot.engine.set_option(name, value).await {
+                self.console.log_line(format!("err: {}", err));
+            }
+        }
+    }
+
+    /// Enable or disable an engine, starting or stopping its search if needed.
+    async fn set_engine_enabled(&mut self, ix: usize, enabled: bool) -> Result<()> {
+        let slot = self.engine.get_mut(ix)?;
+        slot.enabled = enabled;
+        match enabled {
+            true => self.update_engine_position(ix).await,
+            false if slot.is_running() => slot.engine.stop().await,
+            false => Ok(()),
+        }
+    }
+
+    async fn on_engine_command(&mut self, cmd: EngineCommand) {
+        let selected = self.engine.selected();
+        let (ix, res) = match cmd {
+            EngineCommand::Load(path) => (selected, self.load_engine(selected, path).await),
+            EngineCommand::Add(path) => {
+                let res = self.add_engine(path).await;
+                (self.engine.slots.len() - 1, res)
+            }
+            EngineCommand::Restart(ix) => {
+                let ix = ix.unwrap_or(selected);
+                if let Ok(slot) = self.engine.get_mut(ix) {
+                    slot.restarts = 0;
+                }
+                (ix, self.restart_engine(ix).await)
+            }
+            EngineCommand::Unload(ix) => {
+                let ix = ix.unwrap_or(selected);
+                (ix, self.unload_engine(ix).await)
+            }
+            EngineCommand::Enable(ix) => (ix, self.set_engine_enabled(ix, true).await),
+            EngineCommand::Disable(ix) => (ix, self.set_engine_enabled(ix, false).await),
+            EngineCommand::Select(ix) => (ix, self.engine.select(ix)),
+            EngineCommand::List => {
+                self.log_engines();
+                return;
+            }
+            EngineCommand::AutoRestart(enabled) => {
+                self.engine_autorestart = enabled;
+                self.console
+                    .log_line(format!("engine autorestart: {}", enabled));
+                return;
+            }
+            EngineCommand::LogEvals(enabled) => {
+                self.log_evaluations = enabled;
+                self.console
+                    .log_line(format!("log evaluations: {}", enabled));
+                return;
+            }
+        };
+        match res {
+            Ok(_) => {
+                self.status_error = None;
+                self.log_engines();
+            }
+            Err(err) => self.report_error(format!("engine {}: {}", ix + 1, err)),
+        }
+    }
+
+    fn log_engines(&mut self) {
+        let lines = self
+            .engine
+            .slots
+            .iter()
+            .enumerate()
+            .map(|(ix, slot)| {
+                format!(
+                    "engine {}{}: {} ({}){}",
+                    ix + 1,
+                    if ix == self.engine.selected() {
+                        "*"
+                    } else {
+                        ""
+                    },
+                    slot.name(),
+                    slot.path.as_deref().unwrap_or("-"),
+                    if slot.enabled { "" } else { " [disabled]" },
+                )
+            })
+            .collect::<Vec<_>>();
+        for line in lines {
+            self.console.log_line(line);
+        }
+    }
+
     fn update_trees(&mut self) {
         self.update_move_tree();
         self.update_history_tree();
+        // the candidates of a move being typed belong to the previous position
+        self.update_move_input();
     }
 
+    /// Build the legal moves tree, with a node for each group of moves open unless the moves
+    /// aren't grouped.
     fn update_move_tree(&mut self) {
-        let moves = self.board.get_legal_moves();
-        let items = moves
+        let grouping = self.move_grouping;
+        let mut groups = vec![Vec::new(); grouping.names().len()];
+        for mov in self.board.get_legal_moves() {
+            let san = self.board.san(&mov);
+            groups[grouping.group_of(&san)].push((mov, san));
+        }
+        let groups = grouping
+            .names()
             .iter()
-            .map(|m| TreeItem::new_leaf(format!("{}", m)))
+            .zip(groups)
+            .filter(|(_, moves)| !moves.is_empty())
             .collect::<Vec<_>>();
+        let leaves = |moves: &[(Move, String)]| {
+            moves
+                .iter()
+                .map(|(_, san)| TreeItem::new_leaf(san.clone()))
+                .collect::<Vec<_>>()
+        };
+        let items = match grouping {
+            MoveGrouping::Off => groups.iter().flat_map(|(_, moves)| leaves(moves)).collect(),
+            _ => groups
+                .iter()
+                .map(|(name, moves)| {
+                    TreeItem::new(format!("{} ({})", name, moves.len()), leaves(moves))
+                })
+                .collect::<Vec<_>>(),
+        };
+        self.moves_tree_moves = groups
+            .into_iter()
+            .map(|(_, moves)| moves.into_iter().map(|(mov, _)| mov).collect())
+            .collect();
         self.moves_tree = StatefulTree::with_items(items);
+        if grouping != MoveGrouping::Off {
+            for group in 0..self.moves_tree.items.len() {
+                self.moves_tree.state.open(vec![group]);
+            }
+        }
     }
 
     fn update_history_tree(&mut self) {
         let history = self.board.get_history();
-        let chunks = history.chunks_exact(2);
-        let (len, remainder) = (chunks.len(), chunks.remainder());
-        let mut items = chunks
+        // the passes take a turn of their own, shown as `--`
+        let passed = |ply: usize| self.game.passes().contains(&ply).then_some(None);
+        let turns = history
+            .iter()
             .enumerate()
-            .map(|(ix, movs)| {
-                TreeItem::new(
-                    format!("{}. ", ix),
-                    vec![
-                        TreeItem::new_leaf(movs[0].to_string()),
-                        TreeItem::new_leaf(movs[1].to_string()),
-                    ],
-                )
+            .flat_map(|(ix, mov)| passed(ix).into_iter().chain([Some((mov, ix + 1))]))
+            .chain(passed(history.len()))
+            .collect::<Vec<_>>();
+        let items = turns
+            .chunks(2)
+            .enumerate()
+            .map(|(ix, turns)| {
+                let leaves = turns
+                    .iter()
+                    .flat_map(|turn| match turn {
+                        Some((mov, ply)) => self.history_leaves(mov, *ply),
+                        None => vec![TreeItem::new_leaf("--")],
+                    })
+                    .collect::<Vec<_>>();
+                TreeItem::new(format!("{}. ", ix), leaves)
             })
             .collect::<Vec<_>>();
-        if remainder.len() == 1 {
-            let last = remainder[0];
-            items.push(TreeItem::new(
-                format!("{}. ", len),
-                vec![TreeItem::new_leaf(last.to_string())],
-            ));
-        }
         self.history_tree = StatefulTree::with_items(items);
         self.history_tree.last();
     }
 
+    /// The move of the history at `ply`, followed by the variations played instead of it.
+    fn history_leaves(&self, mov: &Move, ply: usize) -> Vec<TreeItem<'a>> {
+        let variations = self
+            .game
+            .variations()
+            .iter()
+            .filter(|v| v.ply + 1 == ply)
+            .map(|v| {
+                let line = v.moves.iter().map(|m| m.to_string()).collect::<Vec<_>>();
+                TreeItem::new_leaf(format!("({})", line.join(" ")))
+            });
+        std::iter::once(TreeItem::new_leaf(self.annotated_move(mov, ply)))
+            .chain(variations)
+            .collect()
+    }
+
+    /// The move followed by the symbol of its judgement, if the game was analysed.
+    fn annotated_move(&self, mov: &Move, ply: usize) -> String {
+        match self.game.judgement(ply) {
+            Some(judgement) => format!("{}{}", mov, judgement.symbol()),
+            None => mov.to_string(),
+        }
+    }
+
+    /// Show another board, keeping the display settings of the current one.
+    fn replace_board(&mut self, mut board: Board) {
+        board.set_flipped(self.flipped_board);
+        board.set_overlays(self.overlays);
+        board.set_coordinates(self.coordinates);
+        board.set_palette(self.palette());
+        board.set_piece_set(self.piece_set);
+        board.set_cursor(self.board_cursor);
+        self.board = board;
+    }
+
     async fn set_position(&mut self, fen: String) {
         match Board::from_fen(fen.clone()) {
             Ok(b) => {
-                self.board = b;
-                self.update_engine_position().await.unwrap();
+                self.replace_board(b);
+                self.game.reset(fen);
+                self.sync_engine_position().await;
                 self.update_trees();
             }
             Err(err) => self
@@ -128,7 +641,10 @@ impl<'a> App<'a> {
 
     async fn drop_piece(&mut self, pos: Position) -> Result<()> {
         match self.board.drop_piece(pos) {
-            Ok(_) => self.update_engine_position().await,
+            Ok(_) => {
+                self.sync_engine_position().await;
+                Ok(())
+            }
             Err(err) => {
                 self.console.log_line(format!("err: {}", err));
                 Err(err)
@@ -136,21 +652,603 @@ impl<'a> App<'a> {
         }
     }
 
-    async fn update_engine_position(&mut self) -> Result<()> {
+    /// Whether the arrow keys drive the board cursor. The panes beside the board keep them
+    /// for scrolling while they're open.
+    fn board_has_focus(&self) -> bool {
+        self.tabs.index == 0 && matches!(self.secondary_pane, SecondaryBoardPane::None)
+    }
+
+    /// Move the board cursor by rows and columns as seen on screen, showing it on the second
+    /// rank of the side to move when it's hidden.
+    fn move_cursor(&mut self, rows: i8, cols: i8) {
+        let ix = match self.board_cursor {
+            Some(pos) => {
+                let rows = match self.flipped_board {
+                    true => -rows,
+                    false => rows,
+                };
+                let ix = pos.as_ix() as i8;
+                let row = (ix / 8 + rows).clamp(0, 7);
+                let col = (ix % 8 + cols).clamp(0, 7);
+                (row * 8 + col) as u8
+            }
+            // e2 or e7
+            None if self.board.white_to_move() => 52,
+            None => 12,
+        };
+        self.board_cursor = Some(Position::Index { ix });
+        self.board.set_cursor(self.board_cursor);
+    }
+
+    /// Grab the piece under the board cursor, or drop the grabbed piece there.
+    async fn select_cursor_square(&mut self) {
+        let pos = match self.board_cursor {
+            Some(pos) => pos,
+            None => return self.move_cursor(0, 0),
+        };
+        if self.board.has_grabbed_piece() {
+            if self.drop_piece(pos).await.is_ok() {
+                self.update_trees();
+            }
+        } else if self.board.grab_piece(pos).is_err() {
+            // no piece of the side to move under the cursor
+        }
+    }
+
+    /// Release the grabbed piece, or hide the board cursor when there's none.
+    fn release_cursor(&mut self) {
+        if self.board.has_grabbed_piece() {
+            self.board.release_piece();
+        } else {
+            self.board_cursor = None;
+            self.board.set_cursor(None);
+        }
+    }
+
+    /// Start typing a move on the Board tab, `/` opens the input without a first character.
+    fn start_move_input(&mut self, c: char) {
+        self.move_input = Some(MoveInput::default());
+        match c {
+            '/' => self.update_move_input(),
+            c => self.type_move_char(c),
+        }
+    }
+
+    fn type_move_char(&mut self, c: char) {
+        if let Some(input) = self.move_input.as_mut() {
+            input.text.push(c);
+        }
+        self.update_move_input();
+    }
+
+    /// Filter the legal moves by the typed text, matching their SAN or UCI.
+    fn update_move_input(&mut self) {
+        let input = match self.move_input.as_mut() {
+            Some(input) => input,
+            None => return,
+        };
+        let text = normalize_move(&input.text);
+        let uci = input.text.to_ascii_lowercase();
+        input.candidates = self
+            .board
+            .get_legal_moves()
+            .into_iter()
+            .map(|mov| (mov, self.board.san(&mov)))
+            .filter(|(mov, san)| {
+                normalize_move(san).starts_with(&text) || mov.as_uci().starts_with(&uci)
+            })
+            .collect();
+    }
+
+    fn delete_move_char(&mut self) {
+        if let Some(input) = self.move_input.as_mut() {
+            if input.text.pop().is_none() {
+                return self.close_move_input();
+            }
+        }
+        self.update_move_input();
+    }
+
+    fn close_move_input(&mut self) {
+        self.move_input = None;
+    }
+
+    /// Play the typed move if it's the only candidate or spelled out in full.
+    async fn play_move_input(&mut self) {
+        let mov = match self.move_input.as_ref().and_then(|input| input.chosen()) {
+            Some(mov) => mov,
+            None => return,
+        };
+        self.close_move_input();
+        self.play_move(mov).await;
+    }
+
+    /// Play the move selected in the legal moves pane, or open or close the selected group.
+    async fn play_selected_move(&mut self) {
+        match self.selected_tree_move() {
+            Some(mov) => self.play_move(mov).await,
+            None => self.moves_tree.toggle(),
+        }
+    }
+
+    async fn play_move(&mut self, mov: Move) {
+        if let Err(err) = self.board.make_move(mov) {
+            self.console.log_line(format!("err: {}", err));
+        }
+        self.sync_engine_position().await;
+        self.update_trees();
+    }
+
+    /// Move highlighted on the board before it's played: the typed one, or the one selected
+    /// in the legal moves pane while it's open.
+    pub fn selected_move(&self) -> Option<Move> {
+        match &self.move_input {
+            Some(input) => input.chosen(),
+            None if matches!(self.secondary_pane, SecondaryBoardPane::MovesTree) => {
+                self.selected_tree_move()
+            }
+            None => None,
+        }
+    }
+
+    /// Move of the selected leaf of the legal moves tree.
+    fn selected_tree_move(&self) -> Option<Move> {
+        let selected = self.moves_tree.state.selected();
+        let (group, ix) = match (self.move_grouping, selected.as_slice()) {
+            (MoveGrouping::Off, [ix]) => (0, *ix),
+            (MoveGrouping::Off, _) => return None,
+            (_, [group, ix]) => (*group, *ix),
+            _ => return None,
+        };
+        self.moves_tree_moves.get(group)?.get(ix).copied()
+    }
+
+    fn cycle_move_grouping(&mut self) {
+        self.move_grouping = match self.move_grouping {
+            MoveGrouping::Piece => MoveGrouping::Kind,
+            MoveGrouping::Kind => MoveGrouping::Off,
+            MoveGrouping::Off => MoveGrouping::Piece,
+        };
+        self.update_move_tree();
+    }
+
+    /// Send the current position to the engines, handling engine failures.
+    async fn sync_engine_position(&mut self) {
+        self.cancel_analysis().await;
+        self.cancel_threat_search().await;
+        self.game.sync(&self.board.get_history());
+        for ix in 0..self.engine.slots.len() {
+            if let Err(err) = self.update_engine_position(ix).await {
+                self.on_engine_failure(ix, err).await;
+            }
+        }
+        self.update_best_move_highlights();
+    }
+
+    async fn update_engine_position(&mut self, ix: usize) -> Result<()> {
         let fen = self.board.as_fen();
-        self.engine.set_position(fen.as_str()).await?;
-        self.restart_search().await?;
+        let slot = &mut self.engine.slots[ix];
+        slot.evaluation = Evaluation::default();
+        slot.info = SearchInfo::default();
+        slot.engine.set_position(fen.as_str()).await?;
+        self.restart_search(ix).await?;
         Ok(())
     }
 
-    async fn restart_search(&mut self) -> Result<()> {
-        if self.searching {
-            self.engine.stop().await?;
-            self.engine.go_infinite().await?;
+    async fn restart_search(&mut self, ix: usize) -> Result<()> {
+        let slot = &mut self.engine.slots[ix];
+        if self.searching && slot.is_active() {
+            // searchmoves are only valid for the position they were given for
+            self.search_params.searchmoves.clear();
+            slot.engine.stop().await?;
+            slot.engine.go(&self.search_params).await?;
         }
         Ok(())
     }
 
+    /// Start a search in all the enabled engines.
+    async fn start_search(&mut self, params: SearchParams) {
+        self.cancel_analysis().await;
+        self.cancel_threat_search().await;
+        self.search_params = params;
+        self.searching = true;
+        for ix in 0..self.engine.slots.len() {
+            let slot = &mut self.engine.slots[ix];
+            if !slot.is_active() {
+                continue;
+            }
+            let res = match slot.engine.stop().await {
+                Ok(_) => slot.engine.go(&self.search_params).await,
+                Err(err) => Err(err),
+            };
+            if let Err(err) = res {
+                self.on_engine_failure(ix, err).await;
+            }
+        }
+    }
+
+    /// Stop the search in all the enabled engines.
+    async fn stop_search(&mut self) {
+        self.cancel_analysis().await;
+        self.cancel_threat_search().await;
+        self.searching = false;
+        for ix in 0..self.engine.slots.len() {
+            let slot = &mut self.engine.slots[ix];
+            if !slot.is_active() {
+                continue;
+            }
+            if let Err(err) = slot.engine.stop().await {
+                self.on_engine_failure(ix, err).await;
+            }
+        }
+    }
+
+    /// Report the best moves of finished searches, and stop searching once all the engines
+    /// finished a search with limits.
+    fn check_finished_searches(&mut self) {
+        let multiple = self.engine.slots.len() > 1;
+        for (ix, slot) in self.engine.slots.iter_mut().enumerate() {
+            if let Some(bestmove) = slot.engine.take_bestmove() {
+                match multiple {
+                    true => self
+                        .console
+                        .log_line(format!("bestmove {}: {}", ix + 1, bestmove)),
+                    false => self.console.log_line(format!("bestmove: {}", bestmove)),
+                }
+            }
+        }
+        if !self.searching || self.search_params.is_infinite() {
+            return;
+        }
+        let still_searching = self
+            .engine
+            .slots
+            .iter()
+            .any(|s| s.is_active() && s.engine.is_searching());
+        if !still_searching {
+            self.searching = false;
+            self.console
+                .log_line(format!("search finished: go {}", self.search_params));
+        }
+    }
+
+    /// Search every position of the game with the selected engine, one at a time. The
+    /// progress is checked on every tick by `check_analysis`.
+    async fn start_analysis(&mut self, limit: Option<SearchLimit>) {
+        if self.game.len() == 0 {
+            self.console
+                .log_line("err: there are no moves to analyse".to_string());
+            return;
+        }
+        if !self.engine.current().is_running() {
+            self.console
+                .log_line(format!("err: {}", EngineError::NoEngineLoaded));
+            return;
+        }
+        self.stop_search().await;
+        let params = SearchParams {
+            limit: limit.unwrap_or(SearchLimit::Depth(ANALYSIS_DEPTH)),
+            searchmoves: Vec::new(),
+        };
+        self.console.log_line(format!(
+            "analysing {} positions with: go {}",
+            self.game.len() + 1,
+            params
+        ));
+        self.analysis = Some(GameAnalysis::new(params));
+        self.analyse_position().await;
+    }
+
+    /// Start the search of the position the analysis is at.
+    async fn analyse_position(&mut self) {
+        let analysis = match self.analysis.as_mut() {
+            Some(analysis) => analysis,
+            None => return,
+        };
+        let board = match self.game.board_at(analysis.ply) {
+            Ok(board) => board,
+            Err(err) => {
+                self.analysis = None;
+                self.console
+                    .log_line(format!("err: analysis stopped: {}", err));
+                return;
+            }
+        };
+        if let Some(eval) = PlyEval::terminal(&board) {
+            // the game ended in this position, there is nothing left to search
+            self.game.record(analysis.ply, eval);
+            return self.finish_analysis().await;
+        }
+        analysis.white_to_move = board.white_to_move();
+        let slot = self.engine.current_mut();
+        let res = match slot.engine.set_position(&board.as_fen()).await {
+            Ok(_) => slot.engine.go(&analysis.params).await,
+            Err(err) => Err(err),
+        };
+        if let Err(err) = res {
+            self.analysis = None;
+            self.console.log_line("analysis stopped".to_string());
+            self.on_engine_failure(self.engine.selected(), err).await;
+        }
+    }
+
+    /// Record the evaluation of the analysed position once its search finished, and move on
+    /// to the next position.
+    async fn check_analysis(&mut self) {
+        let analysis = match self.analysis.as_mut() {
+            Some(analysis) => analysis,
+            None => return,
+        };
+        let slot = self.engine.current_mut();
+        if !slot.is_running() {
+            self.analysis = None;
+            self.console
+                .log_line("analysis stopped: the engine is not running".to_string());
+            return;
+        }
+        if slot.engine.is_searching() {
+            return;
+        }
+        slot.engine.take_bestmove();
+        if let Some(ev) = slot.engine.get_evaluation().await {
+            if ev.depth > 0 {
+                let sign = match analysis.white_to_move {
+                    true => 1,
+                    false => -1,
+                };
+                let eval = PlyEval {
+                    score: ev.score * sign,
+                    mate: ev.mate * sign,
+                    depth: ev.depth,
+                };
+                self.game.record(analysis.ply, eval);
+            }
+        }
+        analysis.ply += 1;
+        match analysis.ply > self.game.len() {
+            true => self.finish_analysis().await,
+            false => self.analyse_position().await,
+        }
+    }
+
+    async fn finish_analysis(&mut self) {
+        self.analysis = None;
+        let count = |white: bool, judgement: Judgement| {
+            (1..=self.game.len())
+                .filter(|ply| ((ply % 2 == 1) == self.game.white_starts()) == white)
+                .filter(|ply| self.game.judgement(*ply) == Some(judgement))
+                .count()
+        };
+        self.console.log_line("analysis finished:".to_string());
+        for (name, white) in [("white", true), ("black", false)] {
+            self.console.log_line(format!(
+                "  {}: {} inaccuracies, {} mistakes, {} blunders",
+                name,
+                count(white, Judgement::Inaccuracy),
+                count(white, Judgement::Mistake),
+                count(white, Judgement::Blunder),
+            ));
+        }
+        self.update_history_tree();
+        // back to the position on the board
+        self.sync_engine_position().await;
+    }
+
+    /// Stop the analysis if one is running.
+    async fn cancel_analysis(&mut self) {
+        if self.analysis.take().is_none() {
+            return;
+        }
+        self.console.log_line("analysis cancelled".to_string());
+        let ix = self.engine.selected();
+        if let Err(err) = self.engine.current_mut().engine.stop().await {
+            self.on_engine_failure(ix, err).await;
+        }
+    }
+
+    /// Show the squares attacked by the opponent and search for its threat, or hide them.
+    async fn toggle_threat_mode(&mut self) {
+        self.overlays.threats = !self.overlays.threats;
+        self.board.set_overlays(self.overlays);
+        if !self.overlays.threats {
+            self.cancel_threat_search().await;
+            self.board.set_threat_move(None);
+        }
+    }
+
+    /// Search the threat of the opponent when the position changed and the selected engine is
+    /// idle, and show it once the search is done. Searches of the user take precedence.
+    async fn check_threat_search(&mut self) {
+        let fen = self.board.as_fen();
+        let ix = self.engine.selected();
+        match self.threat_search.as_mut() {
+            Some(search) if search.pending => {
+                let slot = self.engine.current_mut();
+                if slot.engine.is_searching() {
+                    return;
+                }
+                search.pending = false;
+                let threat = slot
+                    .engine
+                    .take_bestmove()
+                    .and_then(|bestmove| parse_move_values(&bestmove.mov).ok());
+                if search.fen == fen {
+                    self.board.set_threat_move(threat);
+                }
+                // back to the position on the board
+                if let Err(err) = self.update_engine_position(ix).await {
+                    self.on_engine_failure(ix, err).await;
+                }
+                return;
+            }
+            Some(search) if search.fen == fen => return,
+            _ => {}
+        }
+        let slot = self.engine.current();
+        if !self.overlays.threats
+            || self.searching
+            || !slot.is_running()
+            || slot.engine.is_searching()
+        {
+            // a stopped search may still have to report its best move
+            return;
+        }
+        self.board.set_threat_move(None);
+        if self.board.in_check() {
+            // passing is not legal, the threat is the check itself
+            self.threat_search = Some(ThreatSearch {
+                fen,
+                pending: false,
+            });
+            return;
+        }
+        let mut null_move = self.board.clone();
+        null_move.pass_turn();
+        let params = SearchParams {
+            limit: SearchLimit::Depth(THREAT_DEPTH),
+            searchmoves: Vec::new(),
+        };
+        let slot = self.engine.current_mut();
+        let res = match slot.engine.set_position(&null_move.as_fen()).await {
+            Ok(_) => slot.engine.go(&params).await,
+            Err(err) => Err(err),
+        };
+        match res {
+            Ok(_) => self.threat_search = Some(ThreatSearch { fen, pending: true }),
+            Err(err) => self.on_engine_failure(ix, err).await,
+        }
+    }
+
+    /// Stop the null move search if one is running, and send the position on the board back
+    /// to the selected engine.
+    async fn cancel_threat_search(&mut self) {
+        match self.threat_search.take() {
+            Some(search) if search.pending => {}
+            _ => return,
+        }
+        let fen = self.board.as_fen();
+        let ix = self.engine.selected();
+        let slot = self.engine.current_mut();
+        let res = match slot.engine.stop().await {
+            Ok(_) => slot.engine.set_position(&fen).await,
+            Err(err) => Err(err),
+        };
+        if let Err(err) = res {
+            self.on_engine_failure(ix, err).await;
+        }
+    }
+
+    /// Whether the selected engine is searching the null move position, its evaluation
+    /// doesn't belong to the position on the board.
+    fn searching_threat(&self) -> bool {
+        matches!(self.threat_search, Some(ThreatSearch { pending: true, .. }))
+    }
+
+    /// Mark the pieces that can be won, or stop marking them.
+    fn toggle_hanging_pieces(&mut self) {
+        self.overlays.hanging = !self.overlays.hanging;
+        self.board.set_overlays(self.overlays);
+    }
+
+    /// Cycle the rank and file labels: on the borders, inside the squares, hidden.
+    fn cycle_coordinates(&mut self) {
+        self.coordinates = match self.coordinates {
+            Coordinates::Outside => Coordinates::Inside,
+            Coordinates::Inside => Coordinates::Off,
+            Coordinates::Off => Coordinates::Outside,
+        };
+        self.board.set_coordinates(self.coordinates);
+    }
+
+    /// Colour the squares by which side controls them, or stop colouring them.
+    fn toggle_control_heatmap(&mut self) {
+        self.overlays.control = !self.overlays.control;
+        self.board.set_overlays(self.overlays);
+    }
+
+    pub fn palette(&self) -> Palette {
+        self.theme.palette(self.color_support)
+    }
+
+    /// Switch to the named board theme, or list the themes when no name is given.
+    fn set_theme(&mut self, name: Option<String>) {
+        let name = match name {
+            Some(name) => name,
+            None => {
+                let themes = theme_names()
+                    .iter()
+                    .map(|&n| match n == self.theme.name {
+                        true => format!("{} (current)", n),
+                        false => n.to_string(),
+                    })
+                    .collect::<Vec<_>>();
+                self.console
+                    .log_line(format!("themes: {}", themes.join(", ")));
+                return;
+            }
+        };
+        match Theme::by_name(&name) {
+            Ok(theme) => {
+                self.theme = theme;
+                self.board.set_palette(self.palette());
+                self.console
+                    .log_line(format!("theme set to {}", theme.name));
+            }
+            Err(err) => self.console.log_line(format!("err: {}", err)),
+        }
+    }
+
+    /// Switch to the named piece set, or list the sets when no name is given.
+    fn set_piece_set(&mut self, name: Option<String>) {
+        let name = match name {
+            Some(name) => name,
+            None => {
+                let sets = piece_set_names()
+                    .iter()
+                    .map(|&n| match n == self.piece_set.name() {
+                        true => format!("{} (current)", n),
+                        false => n.to_string(),
+                    })
+                    .collect::<Vec<_>>();
+                self.console
+                    .log_line(format!("piece sets: {}", sets.join(", ")));
+                return;
+            }
+        };
+        match PieceSet::by_name(&name) {
+            Ok(set) => {
+                self.piece_set = set;
+                self.board.set_piece_set(set);
+                self.console
+                    .log_line(format!("piece set changed to {}", set.name()));
+            }
+            Err(err) => self.console.log_line(format!("err: {}", err)),
+        }
+    }
+
+    fn log_pgn(&mut self) {
+        match self.game.to_pgn() {
+            Ok(pgn) => {
+                self.console.log_line("PGN of the game:".to_string());
+                for line in pgn.lines() {
+                    self.console.log_line(line.to_string());
+                }
+            }
+            Err(err) => self.console.log_line(format!("err: {}", err)),
+        }
+    }
+
+    fn export_pgn(&mut self, path: String) {
+        let res = self
+            .game
+            .to_pgn()
+            .and_then(|pgn| Ok(std::fs::write(&path, pgn)?));
+        match res {
+            Ok(_) => self.console.log_line(format!("game saved to {}", path)),
+            Err(err) => self.console.log_line(format!("err: {}", err)),
+        }
+    }
+
     fn focus_console(&mut self, buffered: char) {
         self.in_console_input = true;
         self.console.set_active_cursor();
@@ -165,6 +1263,9 @@ impl<'a> App<'a> {
     fn flip_board(&mut self) {
         self.flipped_board = !self.flipped_board;
         self.board.set_flipped(self.flipped_board);
+        if let Some(explorer) = self.pv_explorer.as_mut() {
+            explorer.set_flipped(self.flipped_board);
+        }
     }
 
     fn toggle_moves_tree(&mut self) {
@@ -181,6 +1282,143 @@ impl<'a> App<'a> {
         }
     }
 
+    /// Open the PV explorer with the lines of the running engines, or close it.
+    fn toggle_pv_explorer(&mut self) {
+        if let SecondaryBoardPane::PvExplorer = self.secondary_pane {
+            self.secondary_pane = SecondaryBoardPane::None;
+            self.pv_explorer = None;
+            return;
+        }
+        let multiple = self.engine.slots.len() > 1;
+        let lines = self
+            .engine
+            .slots
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.is_running())
+            .map(|(ix, s)| PvLine {
+                name: match multiple {
+                    true => format!("{}: {}", ix + 1, s.name()),
+                    false => s.name(),
+                },
+                moves: s.evaluation.pv.clone(),
+            })
+            .collect();
+        match PvExplorer::new(self.board.as_fen(), lines, self.flipped_board) {
+            Ok(explorer) => {
+                self.pv_explorer = Some(explorer);
+                self.secondary_pane = SecondaryBoardPane::PvExplorer;
+            }
+            Err(err) => self.console.log_line(format!("err: {}", err)),
+        }
+    }
+
+    /// Add the explored line up to the previewed position to the game, as a variation of the
+    /// move played from the position on the board. A line continuing the game from its last
+    /// move extends it instead, and the board follows it.
+    async fn commit_pv_line(&mut self) {
+        let explorer = match self.pv_explorer.as_ref() {
+            Some(explorer) => explorer,
+            None => return,
+        };
+        if explorer.fen() != self.board.as_fen() {
+            self.console
+                .log_line(format!("err: {}", PvError::PositionChanged));
+            return;
+        }
+        let moves = explorer.played_moves();
+        if moves.is_empty() {
+            self.console
+                .log_line("err: step into the line to choose its moves".to_string());
+            return;
+        }
+        self.pv_explorer = None;
+        self.secondary_pane = SecondaryBoardPane::None;
+        match self.game.add_line(self.board.get_history().len(), &moves) {
+            AddedLine::Known => self
+                .console
+                .log_line("the line is already in the game".to_string()),
+            AddedLine::Variation => {
+                self.console
+                    .log_line("added the line to the game as a variation".to_string());
+                self.update_trees();
+            }
+            AddedLine::Continuation => {
+                self.console
+                    .log_line("continued the game with the line".to_string());
+                self.jump_to_ply(self.game.len()).await;
+            }
+        }
+    }
+
+    fn toggle_eval_graph(&mut self) {
+        self.secondary_pane = match self.secondary_pane {
+            SecondaryBoardPane::EvalGraph => SecondaryBoardPane::None,
+            _ => SecondaryBoardPane::EvalGraph,
+        }
+    }
+
+    /// Show the position after `ply` moves of the game on the board.
+    async fn jump_to_ply(&mut self, ply: usize) {
+        if ply > self.game.len() || ply == self.board.get_history().len() {
+            return;
+        }
+        match self.game.board_at(ply) {
+            Ok(board) => {
+                self.replace_board(board);
+                self.sync_engine_position().await;
+                self.update_trees();
+            }
+            Err(err) => self.console.log_line(format!("err: {}", err)),
+        }
+    }
+
+    /// Record the evaluation of the selected engine for the current ply of the game.
+    fn record_evaluation(&mut self) {
+        let ev = &self.engine.current().evaluation;
+        if ev.depth == 0 {
+            return;
+        }
+        let sign = match self.board.white_to_move() {
+            true => 1,
+            false => -1,
+        };
+        let eval = PlyEval {
+            score: ev.score * sign,
+            mate: ev.mate * sign,
+            depth: ev.depth,
+        };
+        self.game.record(self.board.get_history().len(), eval);
+    }
+
+    fn toggle_best_move_highlight(&mut self) {
+        self.best_move_highlight = match self.best_move_highlight {
+            BestMoveHighlight::Off => BestMoveHighlight::BestMove,
+            BestMoveHighlight::BestMove => BestMoveHighlight::WithReply,
+            BestMoveHighlight::WithReply => BestMoveHighlight::Off,
+        };
+        self.update_best_move_highlights();
+    }
+
+    /// Highlight the first moves of the principal variation of the selected engine.
+    fn update_best_move_highlights(&mut self) {
+        let count = match self.best_move_highlight {
+            BestMoveHighlight::Off => 0,
+            BestMoveHighlight::BestMove => 1,
+            BestMoveHighlight::WithReply => 2,
+        };
+        let moves = self
+            .engine
+            .current()
+            .evaluation
+            .pv
+            .iter()
+            .take(count)
+            .map_while(|mov| parse_move_values(mov).ok())
+            .collect();
+        self.board.set_best_moves(moves);
+    }
+
     fn log_fen(&mut self) {
         self.console
             .log_line("FEN of current position:".to_string());
@@ -191,12 +1429,42 @@ impl<'a> App<'a> {
 /// Trigger Implementations
 impl<'a> App<'a> {
     pub async fn on_tick(&mut self) {
-        if let Some(ev) = self.engine.get_evaluation().await {
-            if ev != self.last_engine_eval {
-                self.console.log_line(format!("eval: {}", ev));
-                self.last_engine_eval = ev;
-            }
+        self.check_engine_health().await;
+        let multiple = self.engine.slots.len() > 1;
+        let threat_slot = match self.searching_threat() {
+            true => Some(self.engine.selected()),
+            false => None,
         };
+        for (ix, slot) in self.engine.slots.iter_mut().enumerate() {
+            if !slot.is_active() || threat_slot == Some(ix) {
+                continue;
+            }
+            slot.info = slot.engine.search_info();
+            if let Some(ev) = slot.engine.get_evaluation().await {
+                if ev != slot.evaluation {
+                    if self.log_evaluations {
+                        match multiple {
+                            true => self.console.log_line(format!("eval {}: {}", ix + 1, ev)),
+                            false => self.console.log_line(format!("eval: {}", ev)),
+                        }
+                    }
+                    slot.evaluation = ev;
+                }
+            };
+        }
+        if self.analysis.is_some() {
+            self.check_analysis().await;
+            return;
+        }
+        if !self.searching_threat() {
+            self.check_finished_searches();
+        }
+        self.check_threat_search().await;
+        if self.searching_threat() {
+            return;
+        }
+        self.update_best_move_highlights();
+        self.record_evaluation();
     }
 
     pub async fn on_enter(&mut self) {
@@ -206,28 +1474,60 @@ impl<'a> App<'a> {
                 Err(err) => self.console.log_line(format!("err: {}", err)),
             };
             self.reset_console();
+            return;
+        }
+        if self.move_input.is_some() {
+            return self.play_move_input().await;
+        }
+        match self.tabs.index {
+            0 if matches!(self.secondary_pane, SecondaryBoardPane::PvExplorer) => {
+                self.commit_pv_line().await
+            }
+            0 if matches!(self.secondary_pane, SecondaryBoardPane::MovesTree) => {
+                self.play_selected_move().await
+            }
+            0 if self.board_has_focus() => self.select_cursor_square().await,
+            2 => self.activate_selected_option().await,
+            _ => {}
         }
     }
 
     pub fn on_next_tab(&mut self) {
+        self.leave_tab();
         self.tabs.next();
     }
 
     pub fn on_prev_tab(&mut self) {
+        self.leave_tab();
         self.tabs.previous();
     }
 
+    /// Drop any input in progress on the current tab before switching to another one.
+    fn leave_tab(&mut self) {
+        self.close_move_input();
+        self.engine.current_mut().options.cancel_editing();
+    }
+
     pub fn on_escape(&mut self) {
         if self.in_console_input {
             self.reset_console();
             self.in_console_input = false;
+        } else if self.move_input.is_some() {
+            self.close_move_input();
+        } else if self.board_has_focus() {
+            self.release_cursor();
         }
+        self.engine.current_mut().options.cancel_editing();
     }
 
     pub fn on_backspace(&mut self) {
+        if self.move_input.is_some() {
+            return self.delete_move_char();
+        }
         if self.in_console_input && self.console.console.cursor().1 > CMD_PREFIX.len() {
             self.console.console.delete_char();
         }
+        self.engine.current_mut().options.delete_char();
     }
 
     pub fn on_delete(&mut self) {
@@ -236,26 +1536,56 @@ impl<'a> App<'a> {
         }
     }
 
-    pub fn on_left(&mut self) {
+    pub async fn on_left(&mut self) {
         if self.in_console_input {
             self.console.console.move_cursor(CursorMove::Back);
             return;
         }
+        if self.board_has_focus() {
+            return self.move_cursor(0, -1);
+        }
+        if self.tabs.index == 2 {
+            self.step_selected_option(false).await;
+        }
         match self.secondary_pane {
             SecondaryBoardPane::MovesTree => self.moves_tree.left(),
             SecondaryBoardPane::History => self.history_tree.left(),
+            SecondaryBoardPane::EvalGraph => {
+                let ply = self.board.get_history().len();
+                self.jump_to_ply(ply.saturating_sub(1)).await
+            }
+            SecondaryBoardPane::PvExplorer => {
+                if let Some(explorer) = self.pv_explorer.as_mut() {
+                    explorer.back();
+                }
+            }
             _ => {}
         }
     }
 
-    pub fn on_right(&mut self) {
+    pub async fn on_right(&mut self) {
         if self.in_console_input {
             self.console.console.move_cursor(CursorMove::Forward);
             return;
         }
+        if self.board_has_focus() {
+            return self.move_cursor(0, 1);
+        }
+        if self.tabs.index == 2 {
+            self.step_selected_option(true).await;
+        }
         match self.secondary_pane {
             SecondaryBoardPane::MovesTree => self.moves_tree.right(),
             SecondaryBoardPane::History => self.history_tree.right(),
+            SecondaryBoardPane::EvalGraph => {
+                let ply = self.board.get_history().len();
+                self.jump_to_ply(ply + 1).await
+            }
+            SecondaryBoardPane::PvExplorer => {
+                if let Some(explorer) = self.pv_explorer.as_mut() {
+                    explorer.forward();
+                }
+            }
             _ => {}
         }
     }
@@ -265,14 +1595,23 @@ impl<'a> App<'a> {
             self.console.move_history_backwards();
             return;
         }
+        if self.board_has_focus() {
+            return self.move_cursor(-1, 0);
+        }
         match self.tabs.index {
             1 => self.console.scroll((-1, 0)),
-            2 => self.help.scroll((-1, 0)),
+            2 => self.engine.current_mut().options.up(),
+            3 => self.help.scroll((-1, 0)),
             _ => {}
         }
         match self.secondary_pane {
             SecondaryBoardPane::MovesTree => self.moves_tree.up(),
             SecondaryBoardPane::History => self.history_tree.up(),
+            SecondaryBoardPane::PvExplorer => {
+                if let Some(explorer) = self.pv_explorer.as_mut() {
+                    explorer.up();
+                }
+            }
             _ => {}
         }
     }
@@ -282,31 +1621,66 @@ impl<'a> App<'a> {
             self.console.move_history_forwards();
             return;
         }
+        if self.board_has_focus() {
+            return self.move_cursor(1, 0);
+        }
         match self.tabs.index {
             1 => self.console.scroll((1, 0)),
-            2 => self.help.scroll((1, 0)),
+            2 => self.engine.current_mut().options.down(),
+            3 => self.help.scroll((1, 0)),
             _ => {}
         }
         match self.secondary_pane {
             SecondaryBoardPane::MovesTree => self.moves_tree.down(),
             SecondaryBoardPane::History => self.history_tree.down(),
+            SecondaryBoardPane::PvExplorer => {
+                if let Some(explorer) = self.pv_explorer.as_mut() {
+                    explorer.down();
+                }
+            }
             _ => {}
         }
     }
 
+    /// Scroll with the mouse wheel, which leaves the board cursor alone.
+    pub fn on_scroll(&mut self, up: bool) {
+        match up {
+            _ if self.board_has_focus() => {}
+            true => self.on_up(),
+            false => self.on_down(),
+        }
+    }
+
     pub async fn on_key(&mut self, c: char) {
         match c {
             _ if self.in_console_input => self.console.insert_char(c),
+            _ if self.engine.current().options.is_editing() => {
+                self.engine.current_mut().options.insert_char(c)
+            }
+            _ if self.move_input.is_some() => self.type_move_char(c),
+            // the first character of a move, the shortcut keys are left alone
+            'a'..='g' | 'K' | 'N' | 'R' | 'Q' | 'O' | '/' if self.tabs.index == 0 => {
+                self.start_move_input(c)
+            }
             'q' => self.should_quit = true,
             ':' => self.focus_console(':'),
             '!' => self.focus_console('!'),
             'S' => self.set_position(INITIAL_POSITION.to_string()).await,
             'M' => self.toggle_moves_tree(),
             'H' => self.toggle_history(),
+            'B' => self.toggle_best_move_highlight(),
+            'P' => self.toggle_pv_explorer(),
+            'E' => self.toggle_eval_graph(),
+            'T' => self.toggle_threat_mode().await,
+            'X' => self.toggle_hanging_pieces(),
+            'C' => self.toggle_control_heatmap(),
+            'L' => self.cycle_coordinates(),
+            'G' => self.cycle_move_grouping(),
             'k' => self.on_up(),
             'j' => self.on_down(),
-            'h' => self.on_left(),
-            'l' => self.on_right(),
+            'h' => self.on_left().await,
+            'l' => self.on_right().await,
+            ' ' if self.board_has_focus() => self.select_cursor_square().await,
             _ => {}
         }
     }
@@ -315,14 +1689,9 @@ impl<'a> App<'a> {
         match cmd {
             Command::Exit => self.should_quit = true,
             Command::SetPosition(pos) => self.set_position(pos).await,
-            Command::StartSeach => match self.engine.go_infinite().await {
-                Ok(_) => self.searching = true,
-                Err(err) => self.console.log_line(format!("err: {}", err)),
-            },
-            Command::StopSearch => match self.engine.stop().await {
-                Ok(_) => self.searching = false,
-                Err(err) => self.console.log_line(format!("err: {}", err)),
-            },
+            Command::StartSeach => self.start_search(SearchParams::default()).await,
+            Command::Go(params) => self.start_search(params).await,
+            Command::StopSearch => self.stop_search().await,
             Command::MakeMove(mov) => {
                 let mov = match mov {
                     ParsedMove::Basic { mov } => mov,
@@ -332,29 +1701,54 @@ impl<'a> App<'a> {
                 if let Err(err) = self.board.make_move(mov) {
                     self.console.log_line(format!("err: {}", err));
                 };
-                self.update_engine_position().await.unwrap();
+                self.sync_engine_position().await;
                 self.update_trees();
             }
             Command::PassTurn => {
                 self.board.pass_turn();
+                self.game.pass(self.board.get_history().len());
+                self.sync_engine_position().await;
                 self.update_trees();
             }
             Command::FlipBoard => self.flip_board(),
             Command::GetFen => self.log_fen(),
+            Command::GetPgn => self.log_pgn(),
+            Command::ExportPgn(path) => self.export_pgn(path),
+            Command::Analyze(limit) => self.start_analysis(limit).await,
+            Command::Theme(name) => self.set_theme(name),
+            Command::PieceSet(name) => self.set_piece_set(name),
+            Command::SetOption(args) => {
+                let (name, value) = self.engine.current().options.split_assignment(&args);
+                self.set_engine_option(name, value).await
+            }
+            Command::Engine(cmd) => self.on_engine_command(cmd).await,
         }
     }
 
     pub async fn on_mouse(&mut self, event: MouseEvent) {
         match event.kind {
             MouseEventKind::Down(MouseButton::Left) => {
-                if let Some(p) = get_relative_positions(event, self.flipped_board) {
+                if let Some(ix) = self.clicked_tab(event) {
+                    self.leave_tab();
+                    self.tabs.index = ix;
+                } else if let Some(ply) = self.clicked_ply(event) {
+                    self.jump_to_ply(ply).await;
+                } else if let Some(row) = clicked_row(self.hit_areas.history, event) {
+                    self.history_tree.click(row);
+                } else if let Some(row) = clicked_row(self.hit_areas.moves_tree, event) {
+                    self.moves_tree.click(row);
+                } else if let Some(p) = self.clicked_square(event) {
                     self.piece_to_grab = Some(p);
                 }
             }
+            MouseEventKind::Drag(MouseButton::Left) => self.drag_piece(event),
+            MouseEventKind::Down(MouseButton::Right) => self.cancel_grab(),
             MouseEventKind::Up(MouseButton::Left) => {
-                let pos = match get_relative_positions(event, self.flipped_board) {
+                let pos = match self.clicked_square(event) {
                     Some(p) => p,
-                    None => return, // out of bounds
+                    // dropped off the board
+                    None if self.piece_to_grab.is_some() => return self.cancel_grab(),
+                    None => return,
                 };
                 match self.piece_to_grab {
                     Some(p) if p == pos => {
@@ -366,45 +1760,114 @@ impl<'a> App<'a> {
                             // tried to grab a piece that is not there
                         }
                     }
-                    Some(p) => {
-                        if self.board.grab_piece(p).is_ok()
-                            && self.board.in_bounds(pos)
-                            && (self.drop_piece(pos).await).is_ok()
-                        {
-                            self.update_trees();
-                        };
-                    }
+                    Some(p) => self.grab_and_drop(p, pos).await,
                     None => {}
                 }
                 self.piece_to_grab = None;
+                self.drag = None;
+                self.board.set_drag(None);
             }
             _ => {}
         }
     }
-}
 
-/// Get the clicked position relative to the board.
-fn get_relative_positions(event: MouseEvent, flipped: bool) -> Option<Position> {
-    // tui-rs makes it dificult to calculate the position of a mouse click relative to a widget
-    // the workaround is knowing that the board always starts at the same absolute position in the screen (x=1, y=3)
-    // and the squares have a fixed size (4w 1h).
-    if event.column < 1 || event.row < 3 || event.column > 33 || event.row > 19 {
-        return None;
+    /// Move the piece on `from` to `to`, as when a piece is dragged across the board.
+    async fn grab_and_drop(&mut self, from: Position, to: Position) {
+        if self.board.grab_piece(from).is_err() || !self.board.in_bounds(to) {
+            return;
+        }
+        if self.drop_piece(to).await.is_ok() {
+            self.update_trees();
+        }
     }
-    if let Some(col) = event.column.checked_sub(1) {
-        let col = col / 4;
-        if let Some(row) = event.row.checked_sub(2) {
-            let row = row / 2;
-            if let Some(row) = row.checked_sub(1) {
-                return Some(Position::Relative {
-                    col: col as u8,
-                    row: row as u8,
-                    flip: flipped,
-                });
-            }
+}
+
+/// Mouse helpers, resolving clicks against the areas drawn in the last frame.
+impl<'a> App<'a> {
+    /// Move the piece pressed on with the pointer, grabbing it on the first drag event so
+    /// its legal moves are highlighted.
+    fn drag_piece(&mut self, event: MouseEvent) {
+        let from = match self.piece_to_grab {
+            Some(p) => p,
+            None => return,
+        };
+        if self.drag.is_none() && self.board.grab_piece(from).is_err() {
+            // nothing to drag from the pressed square
+            return;
         }
+        self.drag = Some(Drag {
+            from,
+            column: event.column,
+            row: event.row,
+            target: self.clicked_square(event),
+        });
+        self.board.set_drag(self.drag);
+    }
+
+    /// Put back the grabbed or dragged piece.
+    fn cancel_grab(&mut self) {
+        self.piece_to_grab = None;
+        self.drag = None;
+        self.board.set_drag(None);
+        self.board.release_piece();
+    }
+
+    /// Index of the tab whose title is under the mouse.
+    fn clicked_tab(&self, event: MouseEvent) -> Option<usize> {
+        self.hit_areas
+            .tabs
+            .iter()
+            .position(|area| contains(*area, event))
+    }
+
+    /// Square of the board under the mouse, if the board is shown.
+    fn clicked_square(&self, event: MouseEvent) -> Option<Position> {
+        let area = self.hit_areas.board?;
+        get_relative_positions(event, area, self.flipped_board)
+    }
+
+    /// The ply plotted at the clicked column of the evaluation graph.
+    fn clicked_ply(&self, event: MouseEvent) -> Option<usize> {
+        let area = self.hit_areas.eval_graph.filter(|a| contains(*a, event))?;
+        let max = self.game.len().max(1) as f64;
+        let width = area.width.saturating_sub(1).max(1) as f64;
+        let ply = ((event.column - area.x) as f64 * max / width).round() as usize;
+        Some(ply.min(self.game.len()))
+    }
+}
+
+/// Row under the mouse of a tree drawn in `area`, counting from its first visible row.
+fn clicked_row(area: Option<Rect>, event: MouseEvent) -> Option<usize> {
+    match area {
+        Some(area) if contains(area, event) => Some((event.row - area.y) as usize),
+        _ => None,
     }
-    None
+}
+
+fn contains(area: Rect, event: MouseEvent) -> bool {
+    area.x <= event.column
+        && event.column < area.right()
+        && area.y <= event.row
+        && event.row < area.bottom()
+}
+
+/// Square under the mouse for a board drawn in `area`, borders included, with the square
+/// size the board widget picks for that area.
+fn get_relative_positions(event: MouseEvent, area: Rect, flipped: bool) -> Option<Position> {
+    let square = SquareSize::fit(area.width, area.height);
+    // skip the borders
+    let col = event.column.checked_sub(area.x + 1)? / square.width;
+    let row = event.row.checked_sub(area.y + 1)? / square.height;
+    // the board may be clipped on small terminals
+    let visible = event.column < area.right() && event.row < area.bottom();
+    if col >= 8 || row >= 8 || !visible {
+        return None;
+    }
+    Some(Position::Relative {
+        col: col as u8,
+        row: row as u8,
+        flip: flipped,
+    })
 }
 
 /// Keeps the state of the tabs in the UI.
@@ -414,7 +1877,7 @@ pub struct TabsState<'a> {
 }
 
 impl<'a> TabsState<'a> {
-    pub fn new(titles: Vec<&'a str>) -> TabsState {
+    pub fn new(titles: Vec<&'a str>) -> TabsState<'a> {
         TabsState { titles, index: 0 }
     }
     pub fn next(&mut self) {
@@ -430,52 +1893,16 @@ impl<'a> TabsState<'a> {
     }
 }
 
-/// An engine that does nothing, used by default when the user does not provide an engine.
-pub struct NoopEngine {}
+#[cfg(test)]
+mod test {
+    use crate::app::normalize_move;
 
-#[async_trait]
-impl ChessEngine for NoopEngine {
-    async fn start_uci(&mut self) -> Result<()> {
-        Ok(())
-    }
-
-    async fn new_game(&mut self) -> Result<()> {
-        Ok(())
-    }
-
-    async fn set_position(&mut self, _position: &str) -> Result<()> {
-        Ok(())
-    }
-
-    async fn go_infinite(&mut self) -> Result<()> {
-        Ok(())
-    }
-
-    async fn go_depth(&mut self, _plies: usize) -> Result<()> {
-        Ok(())
-    }
-
-    async fn go_time(&mut self, _ms: usize) -> Result<()> {
-        Ok(())
-    }
-
-    async fn go_mate(&mut self, _mate_in: usize) -> Result<()> {
-        Ok(())
-    }
-
-    async fn stop(&mut self) -> Result<()> {
-        Ok(())
-    }
-
-    async fn get_evaluation(&mut self) -> Option<Evaluation> {
-        None
-    }
-
-    async fn get_options(&mut self) -> Result<Vec<EngineOption>> {
-        Ok(Vec::new())
-    }
-
-    async fn set_option(&mut self, _option: String, _value: String) -> Result<()> {
-        Ok(())
+    #[test]
+    fn test_normalize_move() {
+        assert_eq!(normalize_move("Nxe5+"), normalize_move("ne5"));
+        assert_eq!(normalize_move("O-O"), normalize_move("0-0"));
+        assert_eq!(normalize_move("e8=Q"), normalize_move("e8q"));
+        assert_ne!(normalize_move("bxc3"), normalize_move("Bxc3"));
+        assert_ne!(normalize_move("Bc3"), normalize_move("bc3"));
     }
 }
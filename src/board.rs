@@ -3,14 +3,15 @@ use std::fmt::Display;
 use anyhow::Result;
 use thiserror::Error;
 use tui::{
-    layout::Constraint,
-    style::{Color, Style},
-    widgets::{Block, Borders, Cell, Row, Table, Widget},
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Widget},
 };
 
 use crate::{
     fen::Fen,
     piece::{CastleRights, CastleRigthsMask, Piece, PieceError},
+    theme::{Palette, PieceSet},
 };
 
 #[derive(Clone, Copy, Error, Debug)]
@@ -31,6 +32,91 @@ pub enum BoardError {
     OutOfBounds,
 }
 
+/// Pieces of each colour attacking a square. A piece attacking a square held by a piece of
+/// its own colour defends it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SquareAttacks {
+    pub white: u8,
+    pub black: u8,
+    /// Value of the cheapest white piece attacking the square.
+    pub cheapest_white: Option<u8>,
+    /// Value of the cheapest black piece attacking the square.
+    pub cheapest_black: Option<u8>,
+}
+
+impl SquareAttacks {
+    fn add(&mut self, piece: Piece) {
+        let (count, cheapest) = match piece.is_white() {
+            true => (&mut self.white, &mut self.cheapest_white),
+            false => (&mut self.black, &mut self.cheapest_black),
+        };
+        *count += 1;
+        *cheapest = Some(cheapest.map_or(piece.value(), |v| v.min(piece.value())));
+    }
+}
+
+/// Largest squares drawn, in rows. Boards with bigger areas are drawn at this size.
+const MAX_SQUARE_HEIGHT: u16 = 5;
+
+/// Size of the squares of the board in terminal cells. Squares are twice as wide as tall, so
+/// they look square with most fonts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SquareSize {
+    pub width: u16,
+    pub height: u16,
+}
+
+impl SquareSize {
+    /// The largest squares of a board that fits, borders included, in the given size. The
+    /// smallest squares are used if none fit, the board is clipped then.
+    pub fn fit(width: u16, height: u16) -> Self {
+        let scale = (width.saturating_sub(2) / 16)
+            .min(height.saturating_sub(2) / 8)
+            .clamp(1, MAX_SQUARE_HEIGHT);
+        Self {
+            width: 2 * scale,
+            height: scale,
+        }
+    }
+
+    /// Width and height of a board with these squares, borders included.
+    pub fn board_size(&self) -> (u16, u16) {
+        (8 * self.width + 2, 8 * self.height + 2)
+    }
+}
+
+/// Where the rank and file labels are drawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Coordinates {
+    /// On the borders of the board, beside the squares.
+    Outside,
+    /// In the corner of the squares of the first file and the bottom rank.
+    Inside,
+    Off,
+}
+
+/// Piece being dragged with the mouse, drawn under the pointer instead of on its square.
+#[derive(Clone, Copy, Debug)]
+pub struct Drag {
+    pub from: Position,
+    /// Screen cell under the pointer.
+    pub column: u16,
+    pub row: u16,
+    /// Square under the pointer, if it's over the board.
+    pub target: Option<Position>,
+}
+
+/// Overlays drawn on top of the board squares.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Overlays {
+    /// Squares attacked by the opponent of the side to move.
+    pub threats: bool,
+    /// Pieces that can be won, see `BoardState::en_prise`.
+    pub hanging: bool,
+    /// Net control of every square, see `Board::get_control_heatmap`.
+    pub control: bool,
+}
+
 #[derive(Clone, Debug)]
 pub struct BoardState {
     pub board: [u8; 64],
@@ -199,6 +285,10 @@ impl BoardState {
         self.grabbed_piece.is_some()
     }
 
+    pub fn release_piece(&mut self) {
+        self.grabbed_piece = None;
+    }
+
     pub fn is_legal(&self, mov: &Move) -> bool {
         self.get_legal_moves().contains(mov)
     }
@@ -255,12 +345,77 @@ impl BoardState {
         self.white_to_move = !self.white_to_move;
         self.update_threatmap();
     }
+
+    /// Attackers of both colours for every square, without looking through other pieces.
+    pub fn attacks(&self) -> [SquareAttacks; 64] {
+        let mut attacks = [SquareAttacks::default(); 64];
+        for ix in 0..64 {
+            let piece = match Piece::try_from(self.board[ix]) {
+                Ok(p) => p,
+                Err(_e) => continue,
+            };
+            for square in piece.get_attacks(&self.board, ix as u8) {
+                attacks[square as usize].add(piece);
+            }
+        }
+        attacks
+    }
+
+    /// Squares holding pieces that are attacked more times than they are defended, or
+    /// attacked by a cheaper piece. Kings are left out, they are in check instead.
+    pub fn en_prise(&self) -> Vec<u8> {
+        let attacks = self.attacks();
+        (0..64)
+            .filter(|&ix| {
+                let piece = match Piece::try_from(self.board[ix]) {
+                    Ok(Piece::WhiteKing) | Ok(Piece::BlackKing) | Err(_) => return false,
+                    Ok(p) => p,
+                };
+                let square = attacks[ix];
+                let (attackers, defenders, cheapest) = match piece.is_white() {
+                    true => (square.black, square.white, square.cheapest_black),
+                    false => (square.white, square.black, square.cheapest_white),
+                };
+                attackers > 0
+                    && (attackers > defenders || cheapest.is_some_and(|v| v < piece.value()))
+            })
+            .map(|ix| ix as u8)
+            .collect()
+    }
+
+    /// Whether the king of the side to move is attacked.
+    pub fn in_check(&self) -> bool {
+        self.checked_king().is_some()
+    }
+
+    /// Square of the king of the side to move when it's in check.
+    pub fn checked_king(&self) -> Option<u8> {
+        let king_code: u8 = match self.white_to_move {
+            true => Piece::WhiteKing.into(),
+            false => Piece::BlackKing.into(),
+        };
+        self.board
+            .iter()
+            .position(|&p| p == king_code)
+            .filter(|&ix| self.threatmap[ix] > 0)
+            .map(|ix| ix as u8)
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct Board {
     state: BoardState,
     flipped_board: bool,
+    best_moves: Vec<(Position, Position)>,
+    overlays: Overlays,
+    coordinates: Coordinates,
+    threat_move: Option<(Position, Position)>,
+    palette: Palette,
+    piece_set: PieceSet,
+    cursor: Option<Position>,
+    selected_move: Option<(Position, Position)>,
+    drag: Option<Drag>,
+    title: String,
 }
 
 impl Board {
@@ -268,6 +423,16 @@ impl Board {
         Ok(Board {
             state: BoardState::from_fen(fen)?,
             flipped_board: false,
+            best_moves: Vec::new(),
+            overlays: Overlays::default(),
+            coordinates: Coordinates::Outside,
+            threat_move: None,
+            palette: Palette::default(),
+            piece_set: PieceSet::Unicode,
+            cursor: None,
+            selected_move: None,
+            drag: None,
+            title: "Board".to_string(),
         })
     }
 
@@ -295,6 +460,10 @@ impl Board {
         self.state.has_grabbed_piece()
     }
 
+    pub fn release_piece(&mut self) {
+        self.state.release_piece()
+    }
+
     pub fn white_to_move(&self) -> bool {
         self.state.white_to_move
     }
@@ -307,6 +476,10 @@ impl Board {
         self.state.pass_turn()
     }
 
+    pub fn in_check(&self) -> bool {
+        self.state.in_check()
+    }
+
     pub fn set_flipped(&mut self, flipped: bool) {
         self.flipped_board = flipped;
     }
@@ -314,6 +487,129 @@ impl Board {
     pub fn get_history(&self) -> Vec<Move> {
         self.state.history.clone()
     }
+
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+    }
+
+    pub fn set_piece_set(&mut self, piece_set: PieceSet) {
+        self.piece_set = piece_set;
+    }
+
+    /// Square of the keyboard cursor, hidden when `None`.
+    pub fn set_cursor(&mut self, cursor: Option<Position>) {
+        self.cursor = cursor;
+    }
+
+    /// Move about to be played, highlighted like a grabbed piece and its destination.
+    pub fn set_selected_move(&mut self, mov: Option<(Position, Position)>) {
+        self.selected_move = mov;
+    }
+
+    pub fn set_drag(&mut self, drag: Option<Drag>) {
+        self.drag = drag;
+    }
+
+    pub fn set_title(&mut self, title: String) {
+        self.title = title;
+    }
+
+    /// The move in standard algebraic notation, i.e. `Nbd2`, `exd5`, `e8=Q+` or `O-O`.
+    /// The move must be legal in the current position.
+    pub fn san(&self, mov: &Move) -> String {
+        let piece = match Piece::try_from(self.state.board[mov.from.as_ix() as usize]) {
+            Ok(p) => p,
+            Err(_) => return mov.as_uci(),
+        };
+        let from = mov.from.to_string();
+        let mut san = match mov.castling {
+            Some(_) if mov.to.as_ix() % 8 > mov.from.as_ix() % 8 => "O-O".to_string(),
+            Some(_) => "O-O-O".to_string(),
+            None => {
+                let letter = char::from(piece).to_ascii_uppercase();
+                let capture =
+                    self.state.board[mov.to.as_ix() as usize] != 0 || mov.en_passant.is_some();
+                let mut san = String::new();
+                if letter == 'P' {
+                    if capture {
+                        san.push_str(&from[..1]);
+                    }
+                } else {
+                    san.push(letter);
+                    san.push_str(&self.disambiguation(mov));
+                }
+                if capture {
+                    san.push('x');
+                }
+                san.push_str(&mov.to.to_string());
+                if let Some(p) = mov.promotion {
+                    san.push('=');
+                    san.push(char::from(p).to_ascii_uppercase());
+                }
+                san
+            }
+        };
+        let mut after = self.clone();
+        if after.make_move(*mov).is_ok() && after.state.in_check() {
+            match after.get_legal_moves().is_empty() {
+                true => san.push('#'),
+                false => san.push('+'),
+            }
+        }
+        san
+    }
+
+    /// File, rank or square needed in SAN to tell the move apart from moves of other pieces
+    /// of the same type to the same square.
+    fn disambiguation(&self, mov: &Move) -> String {
+        let code = self.state.board[mov.from.as_ix() as usize];
+        let from = mov.from.to_string();
+        let others = self
+            .get_legal_moves()
+            .into_iter()
+            .filter(|m| {
+                m.to == mov.to
+                    && m.from != mov.from
+                    && self.state.board[m.from.as_ix() as usize] == code
+            })
+            .map(|m| m.from.to_string())
+            .collect::<Vec<_>>();
+        if others.is_empty() {
+            String::new()
+        } else if others.iter().all(|o| o[..1] != from[..1]) {
+            from[..1].to_string()
+        } else if others.iter().all(|o| o[1..] != from[1..]) {
+            from[1..].to_string()
+        } else {
+            from
+        }
+    }
+
+    /// Find the legal move matching a move in long algebraic notation, i.e. `e7e8q`.
+    pub fn find_legal_move(&self, uci: &str) -> Option<Move> {
+        self.get_legal_moves()
+            .into_iter()
+            .find(|m| m.as_uci() == uci)
+    }
+
+    /// Set the from and to squares of the engine moves to highlight, the best move first
+    /// followed by the expected reply.
+    pub fn set_best_moves(&mut self, moves: Vec<(Position, Position)>) {
+        self.best_moves = moves;
+    }
+
+    pub fn set_overlays(&mut self, overlays: Overlays) {
+        self.overlays = overlays;
+    }
+
+    pub fn set_coordinates(&mut self, coordinates: Coordinates) {
+        self.coordinates = coordinates;
+    }
+
+    /// Set the from and to squares of the move the opponent threatens to play.
+    pub fn set_threat_move(&mut self, mov: Option<(Position, Position)>) {
+        self.threat_move = mov;
+    }
 }
 
 impl Widget for Board {
@@ -321,69 +617,336 @@ impl Widget for Board {
         if area.area() == 0 {
             return;
         }
+        let square = SquareSize::fit(area.width, area.height);
+        let (width, height) = square.board_size();
+        let area = area.intersection(Rect::new(area.x, area.y, width, height));
+        let block = Block::default()
+            .title(self.title.clone())
+            .borders(Borders::ALL);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
         let highlight_squares = self.get_grabbed_piece_highlights();
-        let mut rows = Vec::with_capacity(8);
-        for mut r in 0..8 {
-            if self.flipped_board {
-                r = 7 - r;
-            }
-            let mut row = Vec::with_capacity(8);
+        let heatmap = self.get_control_heatmap();
+        let attacked = self.get_attacked_squares();
+        let en_prise = match self.overlays.hanging {
+            true => self.state.en_prise(),
+            false => Vec::new(),
+        };
+        for row in 0..8 {
+            let r = match self.flipped_board {
+                true => 7 - row,
+                false => row,
+            };
             for c in 0..8 {
                 let ix: u8 = r * 8 + c;
-                let style = self.get_square_style(c, r, &highlight_squares);
-                let text = self.get_piece_text(ix);
-                let cell = Cell::from(text).style(style);
-                row.push(cell);
+                let rect = Rect::new(
+                    inner.x + c as u16 * square.width,
+                    inner.y + row as u16 * square.height,
+                    square.width,
+                    square.height,
+                )
+                .intersection(inner);
+                if rect.area() == 0 {
+                    continue;
+                }
+                let label = match (c + r) % 2 {
+                    0 => self.palette.dark_square,
+                    _ => self.palette.light_square,
+                };
+                let mut style =
+                    self.get_square_style(c, r, &highlight_squares, &heatmap, &attacked);
+                let marked = en_prise.contains(&ix) && !self.palette.reversed_highlights;
+                if en_prise.contains(&ix) {
+                    style = match marked {
+                        true => style.fg(self.palette.en_prise),
+                        false => self.highlight(self.palette.en_prise, c, r),
+                    }
+                    .add_modifier(Modifier::BOLD);
+                }
+                buf.set_style(rect, style);
+                let inside = self.coordinates == Coordinates::Inside;
+                // squares one row high have no room for a label beside the piece, there the
+                // rank label takes the left cell and the piece the right one, leaving no room
+                // for the file label of the corner square
+                let shifted = inside && c == 0 && square.height == 1;
+                if self.drag.is_none_or(|d| d.from.as_ix() != ix) {
+                    let piece_rect = match shifted {
+                        true => Rect::new(rect.x + 1, rect.y, rect.width - 1, rect.height),
+                        false => rect,
+                    };
+                    self.render_piece(ix, piece_rect, square, marked, buf);
+                }
+                // the labels go over the pieces, which may cover the whole square
+                if inside {
+                    // reversed squares show their foreground as the background
+                    let label_style = match style.add_modifier.contains(Modifier::REVERSED) {
+                        true => style.bg(label),
+                        false => style.fg(label),
+                    }
+                    .remove_modifier(Modifier::BOLD);
+                    if c == 0 {
+                        buf.set_string(rect.x, rect.y, rank_label(r), label_style);
+                    }
+                    if row == 7 && rect.height == square.height && !shifted {
+                        let (x, y) = (rect.right() - 1, rect.bottom() - 1);
+                        buf.set_string(x, y, file_label(c), label_style);
+                    }
+                }
+            }
+        }
+        if let Some(drag) = self.drag {
+            self.render_dragged_piece(drag, inner, buf);
+        }
+        if self.coordinates == Coordinates::Outside {
+            // over the left and bottom borders, in the middle of the squares
+            for row in 0..8 {
+                let r = match self.flipped_board {
+                    true => 7 - row,
+                    false => row,
+                };
+                let y = inner.y + row as u16 * square.height + (square.height - 1) / 2;
+                if y < inner.bottom() {
+                    buf.set_string(area.x, y, rank_label(r), Style::default());
+                }
+            }
+            if area.height == height {
+                for c in 0..8 {
+                    let x = inner.x + c as u16 * square.width + (square.width - 1) / 2;
+                    if x < inner.right() {
+                        buf.set_string(x, area.bottom() - 1, file_label(c), Style::default());
+                    }
+                }
             }
-            rows.push(Row::new(row).height(2));
-        }
-        Table::new(rows)
-            .style(Style::default().fg(Color::White))
-            .column_spacing(0)
-            .widths(&[
-                Constraint::Length(4),
-                Constraint::Length(4),
-                Constraint::Length(4),
-                Constraint::Length(4),
-                Constraint::Length(4),
-                Constraint::Length(4),
-                Constraint::Length(4),
-                Constraint::Length(4),
-            ])
-            .block(Block::default().title("Board").borders(Borders::ALL))
-            .render(area, buf);
+        }
     }
 }
 
+/// Label of the rank of the squares in `row` of the board array, row 0 being the 8th rank.
+fn rank_label(row: u8) -> String {
+    ((b'8' - row) as char).to_string()
+}
+
+fn file_label(col: u8) -> String {
+    ((b'a' + col) as char).to_string()
+}
+
 /// Implementation of render helper methods
 impl Board {
-    fn get_piece_text(&self, ix: u8) -> String {
-        let piece = Piece::try_from(self.state.board[ix as usize]);
-        let char = match piece {
-            Ok(p) => p.to_string(),
-            Err(PieceError::NoPieceFound) => String::new(),
-            Err(_e) => String::from("?"), // TODO: Should log issue to console
+    /// Draw the piece on the square with the selected piece set, in the colour of its side.
+    fn render_piece(
+        &self,
+        ix: u8,
+        rect: Rect,
+        square: SquareSize,
+        marked: bool,
+        buf: &mut tui::buffer::Buffer,
+    ) {
+        let piece = match Piece::try_from(self.state.board[ix as usize]) {
+            Ok(p) => p,
+            Err(PieceError::NoPieceFound) => return,
+            Err(_e) => {
+                // TODO: Should log issue to console
+                buf.set_string(rect.x, rect.y, "?", Style::default());
+                return;
+            }
         };
-        match piece {
-            Ok(p) if p.is_white() => format!(" {}", char),
-            Ok(p) if !p.is_white() => format!("\n {}", char),
-            _ => char,
+        let large = square.height > 3;
+        let lines: Vec<String> = match (self.piece_set, square.height) {
+            (PieceSet::Art, 3..) => piece
+                .as_ascii_art(large)
+                .iter()
+                .map(|l| l.to_string())
+                .collect(),
+            (PieceSet::Unicode | PieceSet::Filled, 3..) => piece
+                .as_block_art(large)
+                .iter()
+                .map(|l| l.to_string())
+                .collect(),
+            _ => vec![self.piece_glyph(piece)],
+        };
+        let fg = match marked {
+            true => self.palette.en_prise,
+            false => self.piece_color(piece),
+        };
+        let style = Style::default().fg(fg);
+        let art_width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0) as u16;
+        let art_height = lines.len() as u16;
+        let x = rect.x + square.width.saturating_sub(art_width) / 2;
+        let y = rect.y + square.height.saturating_sub(art_height) / 2;
+        for (line_ix, line) in lines.iter().enumerate() {
+            let y = y + line_ix as u16;
+            if y >= rect.bottom() || x >= rect.right() {
+                continue;
+            }
+            buf.set_stringn(x, y, line, (rect.right() - x) as usize, style);
         }
     }
 
-    fn get_square_style(&self, col: u8, row: u8, highlights: &[Option<Color>]) -> Style {
+    /// Draw the dragged piece as a single character under the pointer, while it's over the
+    /// board.
+    fn render_dragged_piece(&self, drag: Drag, inner: Rect, buf: &mut tui::buffer::Buffer) {
+        let piece = match Piece::try_from(self.state.board[drag.from.as_ix() as usize]) {
+            Ok(p) => p,
+            Err(_) => return,
+        };
+        let inside = inner.x <= drag.column
+            && drag.column < inner.right()
+            && inner.y <= drag.row
+            && drag.row < inner.bottom();
+        if inside {
+            let style = Style::default()
+                .fg(self.piece_color(piece))
+                .add_modifier(Modifier::BOLD);
+            buf.set_string(drag.column, drag.row, self.piece_glyph(piece), style);
+        }
+    }
+
+    /// The piece as a single character of the selected piece set.
+    fn piece_glyph(&self, piece: Piece) -> String {
+        match self.piece_set {
+            PieceSet::Ascii | PieceSet::Art => char::from(piece).to_string(),
+            PieceSet::Unicode => piece.to_string(),
+            PieceSet::Filled => piece.as_filled_unicode_char().to_string(),
+        }
+    }
+
+    fn piece_color(&self, piece: Piece) -> Color {
+        match piece.is_white() {
+            true => self.palette.white_piece,
+            false => self.palette.black_piece,
+        }
+    }
+
+    fn get_square_style(
+        &self,
+        col: u8,
+        row: u8,
+        highlights: &[Option<Color>],
+        heatmap: &[Option<Color>],
+        attacked: &[bool],
+    ) -> Style {
         let ix = row * 8 + col;
+        let drag_target = self.drag.and_then(|d| d.target);
+        if self.cursor.is_some_and(|p| p.as_ix() == ix)
+            || drag_target.is_some_and(|p| p.as_ix() == ix)
+        {
+            return self.highlight(self.palette.cursor, col, row);
+        }
         if let Some(Some(c)) = highlights.get(ix as usize) {
-            return Style::default().bg(*c);
+            return self.highlight(*c, col, row);
+        }
+        match self.selected_move {
+            Some((from, _)) if from.as_ix() == ix => {
+                return self.highlight(self.palette.grabbed, col, row)
+            }
+            Some((_, to)) if to.as_ix() == ix => {
+                return self.highlight(self.palette.legal_move, col, row)
+            }
+            _ => {}
+        }
+        if self.state.checked_king() == Some(ix) && self.state.grabbed_piece != Some(ix) {
+            return self.highlight(self.palette.check, col, row);
+        }
+        if let Some(c) = self.get_best_move_highlight(ix) {
+            if self.state.grabbed_piece != Some(ix) {
+                return self.highlight(c, col, row);
+            }
+        }
+        if let Some((from, to)) = self.threat_move {
+            if from.as_ix() == ix || to.as_ix() == ix {
+                return self.highlight(self.palette.threat, col, row);
+            }
+        }
+        let attacked = attacked[ix as usize];
+        let last_move = self
+            .state
+            .last_move
+            .is_some_and(|m| m.from.as_ix() == ix || m.to.as_ix() == ix);
+        if let Some(Some(c)) = heatmap.get(ix as usize) {
+            if self.state.grabbed_piece != Some(ix) {
+                return self.highlight(*c, col, row);
+            }
         }
         match col + row {
-            _ if self.state.grabbed_piece == Some(ix) => Style::default().bg(Color::LightRed),
-            x if x % 2 != 0 => Style::default().bg(Color::DarkGray),
-            x if x % 2 == 0 => Style::default().bg(Color::Gray),
+            _ if self.state.grabbed_piece == Some(ix) => {
+                self.highlight(self.palette.grabbed, col, row)
+            }
+            _ if last_move => self.highlight(self.palette.last_move, col, row),
+            x if attacked && x % 2 != 0 => self.highlight(self.palette.attacked_dark, col, row),
+            x if attacked && x % 2 == 0 => self.highlight(self.palette.attacked_light, col, row),
+            x if x % 2 != 0 => Style::default().bg(self.palette.dark_square),
+            x if x % 2 == 0 => Style::default().bg(self.palette.light_square),
             _ => panic!("invalid remainder"),
         }
     }
 
+    /// Style of a highlighted square, reversed for palettes asking for it so the highlight
+    /// fills the square and the piece keeps its own colour.
+    fn highlight(&self, color: Color, col: u8, row: u8) -> Style {
+        if !self.palette.reversed_highlights {
+            return Style::default().bg(color);
+        }
+        let square = match (col + row) % 2 {
+            0 => self.palette.light_square,
+            _ => self.palette.dark_square,
+        };
+        Style::default()
+            .fg(color)
+            .bg(square)
+            .add_modifier(Modifier::REVERSED)
+    }
+
+    /// Colour of every square by its net control, the white attackers minus the black ones:
+    /// shades of blue when white controls it and of red when black does. Squares nobody
+    /// controls keep their colour.
+    fn get_control_heatmap(&self) -> Vec<Option<Color>> {
+        if !self.overlays.control {
+            return vec![None; 64];
+        }
+        let (white_shades, black_shades) = (self.palette.control_white, self.palette.control_black);
+        self.state
+            .attacks()
+            .iter()
+            .map(|square| {
+                let net = square.white as i16 - square.black as i16;
+                let shade = (net.unsigned_abs() as usize).min(white_shades.len());
+                match net {
+                    0 => None,
+                    n if n > 0 => Some(white_shades[shade - 1]),
+                    _ => Some(black_shades[shade - 1]),
+                }
+            })
+            .collect()
+    }
+
+    /// Squares attacked by at least one piece of the opponent of the side to move, including
+    /// the ones it defends, for the threat mode overlay.
+    fn get_attacked_squares(&self) -> Vec<bool> {
+        if !self.overlays.threats {
+            return vec![false; 64];
+        }
+        self.state
+            .attacks()
+            .iter()
+            .map(|square| match self.state.white_to_move {
+                true => square.black > 0,
+                false => square.white > 0,
+            })
+            .collect()
+    }
+
+    /// Colour of the square if it's part of the best move or the reply, the best move wins
+    /// when both use the same square.
+    fn get_best_move_highlight(&self, ix: u8) -> Option<Color> {
+        let colors = [self.palette.best_move, self.palette.reply];
+        self.best_moves
+            .iter()
+            .zip(colors)
+            .find(|((from, to), _)| from.as_ix() == ix || to.as_ix() == ix)
+            .map(|(_, c)| c)
+    }
+
     fn get_grabbed_piece_highlights(&self) -> Vec<Option<Color>> {
         let mut highlights = vec![None; 64];
         match self.state.grabbed_piece {
@@ -404,7 +967,7 @@ impl Board {
                     .into_iter()
                     .filter(|m| !copy.leaves_king_in_check(*m))
                     .map(|m| m.to)
-                    .for_each(|p| highlights[p.as_ix() as usize] = Some(Color::LightGreen));
+                    .for_each(|p| highlights[p.as_ix() as usize] = Some(self.palette.legal_move));
                 highlights
             }
             None => highlights,
@@ -530,6 +1093,15 @@ impl Move {
     pub fn set_piece(&mut self, piece: Piece) {
         self.piece = Some(piece);
     }
+
+    /// The move in the long algebraic notation used by UCI, i.e. `e7e8q`.
+    pub fn as_uci(&self) -> String {
+        let mut mov = format!("{}{}", self.from, self.to);
+        if let Some(p) = self.promotion {
+            mov.push(char::from(p).to_ascii_lowercase());
+        }
+        mov
+    }
 }
 
 impl PartialEq for Move {
@@ -565,7 +1137,10 @@ fn move_to_ix(c: u8, r: u8) -> u8 {
 
 #[cfg(test)]
 mod test {
-    use crate::board::{move_to_ix, Position};
+    use crate::{
+        app::INITIAL_POSITION,
+        board::{move_to_ix, Board, BoardState, Overlays, Position, SquareSize},
+    };
 
     #[test]
     fn test_algebraic() {
@@ -603,4 +1178,98 @@ mod test {
             assert_eq!(a, b, "left: {} != right: {}", a.as_ix(), b.as_ix());
         }
     }
+
+    #[test]
+    fn test_san() {
+        let board = Board::from_fen("r3k2r/1P6/8/3p4/4P3/5N2/8/RN2K2R w KQkq".to_string()).unwrap();
+        let san = |uci: &str| board.san(&board.find_legal_move(uci).unwrap());
+        assert_eq!(san("e4d5"), "exd5");
+        assert_eq!(san("e4e5"), "e5");
+        assert_eq!(san("b1d2"), "Nbd2");
+        assert_eq!(san("f3d4"), "Nd4");
+        assert_eq!(san("b7a8q"), "bxa8=Q+");
+        assert_eq!(san("e1g1"), "O-O");
+
+        let board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w -".to_string()).unwrap();
+        assert_eq!(board.san(&board.find_legal_move("a1a8").unwrap()), "Ra8#");
+    }
+
+    #[test]
+    fn test_attacks_and_en_prise() {
+        let ix = |square: &str| {
+            Position::Algebraic {
+                rank: square.as_bytes()[0] - b'a',
+                file: square.as_bytes()[1] - b'1',
+            }
+            .as_ix() as usize
+        };
+        // the knight on c6 is attacked by a pawn, the rooks attack each other and the one on
+        // h8 is also attacked by the bishop
+        let state = BoardState::from_fen("4k2r/8/2n5/3P4/8/8/8/B3K2R w -".to_string()).unwrap();
+        let attacks = state.attacks();
+        assert_eq!(attacks[ix("c6")].white, 1);
+        assert_eq!(attacks[ix("c6")].cheapest_white, Some(1));
+        assert_eq!(attacks[ix("h8")].white, 2);
+        assert_eq!(attacks[ix("h8")].black, 0);
+        assert_eq!(attacks[ix("e5")].black, 1);
+        assert_eq!(attacks[ix("d5")].black, 0);
+
+        let mut en_prise = state.en_prise();
+        en_prise.sort();
+        assert_eq!(
+            en_prise,
+            vec![ix("h8") as u8, ix("c6") as u8, ix("h1") as u8]
+        );
+    }
+
+    #[test]
+    fn test_square_size() {
+        let fit = |width, height| {
+            let square = SquareSize::fit(width, height);
+            (square.width, square.height)
+        };
+        assert_eq!(fit(34, 18), (4, 2));
+        assert_eq!(fit(34, 60), (4, 2));
+        assert_eq!(fit(100, 30), (6, 3));
+        assert_eq!(fit(300, 100), (10, 5));
+        assert_eq!(fit(10, 5), (2, 1));
+        assert_eq!(SquareSize::fit(50, 26).board_size(), (50, 26));
+    }
+
+    #[test]
+    fn test_find_legal_move() {
+        let board = Board::from_fen("k7/6P1/8/8/8/8/8/4K2R w K".to_string()).unwrap();
+        assert_eq!(board.find_legal_move("g7g8n").unwrap().as_uci(), "g7g8n");
+        assert!(board.find_legal_move("e1g1").unwrap().castling.is_some());
+        assert!(board.find_legal_move("g7g8").is_none());
+        assert!(board.find_legal_move("e1e3").is_none());
+    }
+
+    #[test]
+    fn test_checked_king() {
+        let mut board = Board::from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w -".to_string()).unwrap();
+        assert_eq!(board.state.checked_king(), None);
+        board
+            .make_move(board.find_legal_move("a1a8").unwrap())
+            .unwrap();
+        // g8
+        assert_eq!(board.state.checked_king(), Some(6));
+        assert!(board.in_check());
+    }
+
+    #[test]
+    fn test_attacked_squares() {
+        let mut board = Board::from_fen(INITIAL_POSITION.to_string()).unwrap();
+        board.set_overlays(Overlays {
+            threats: true,
+            ..Overlays::default()
+        });
+        let attacked = board.get_attacked_squares();
+        // the pawns attack the whole 6th rank but can't take on the squares they push to
+        assert!((16..24).all(|ix| attacked[ix]));
+        assert!((24..64).all(|ix| !attacked[ix]));
+        // defended pieces are attacked too, the rooks in the corners aren't
+        assert!(attacked[1] && attacked[12]);
+        assert!(!attacked[0]);
+    }
 }
@@ -3,9 +3,17 @@ use clap::Parser;
 #[derive(Parser, Debug, Clone)]
 #[command(author, version, about, long_about)]
 pub struct CLIArgs {
-    /// Path to the engine executable
+    /// Path to the engine executable, can be repeated to attach several engines
     #[clap(short = 'P', long)]
-    pub engine_path: Option<String>,
+    pub engine_path: Vec<String>,
+
+    /// Restart the engine automatically if it crashes or stops responding
+    #[clap(short = 'R', long)]
+    pub engine_autorestart: bool,
+
+    /// Log every evaluation update of the engines in the console
+    #[clap(short = 'E', long)]
+    pub log_evals: bool,
 
     /// Tickrate in milliseconds
     #[clap(short = 'T', long, default_value = "200")]
@@ -5,7 +5,10 @@ use thiserror::Error;
 use tui::style::{Color, Style};
 use tui_textarea::TextArea;
 
-use crate::board::{Move, Position};
+use crate::{
+    board::{Move, Position},
+    engine::{SearchLimit, SearchParams},
+};
 
 pub const CMD_PREFIX: &str = "> ";
 
@@ -119,6 +122,12 @@ pub enum CommandError {
 
     #[error("error parsing move: {mov}")]
     MoveParsingError { mov: String },
+
+    #[error("invalid engine number: {n}")]
+    InvalidEngineNumber { n: String },
+
+    #[error("invalid or missing value for go argument: {arg}")]
+    InvalidGoArgument { arg: String },
 }
 
 #[derive(Debug, Clone)]
@@ -126,11 +135,33 @@ pub enum Command {
     Exit,
     SetPosition(String),
     GetFen,
+    GetPgn,
+    ExportPgn(String),
+    Analyze(Option<SearchLimit>),
+    Theme(Option<String>),
+    PieceSet(Option<String>),
     StartSeach,
+    Go(SearchParams),
     StopSearch,
     MakeMove(ParsedMove),
     PassTurn,
     FlipBoard,
+    SetOption(String),
+    Engine(EngineCommand),
+}
+
+#[derive(Debug, Clone)]
+pub enum EngineCommand {
+    Load(String),
+    Add(String),
+    Restart(Option<usize>),
+    Unload(Option<usize>),
+    Enable(usize),
+    Disable(usize),
+    Select(usize),
+    List,
+    AutoRestart(bool),
+    LogEvals(bool),
 }
 
 impl Command {
@@ -145,12 +176,22 @@ impl Command {
         };
         let cmd = match word {
             "!fen" => Command::GetFen,
+            "!pgn" => Command::GetPgn,
+            ":exportpgn" if command.len() > 11 => {
+                Command::ExportPgn(command[11..].trim().to_string())
+            }
+            ":analyze" => Command::Analyze(parse_analyze_command(&command)?),
+            ":theme" => Command::Theme(command.split_whitespace().nth(1).map(String::from)),
+            ":pieces" => Command::PieceSet(command.split_whitespace().nth(1).map(String::from)),
             "exit" | ":q" => Command::Exit,
             ":passturn" => Command::PassTurn,
             ":flipboard" => Command::FlipBoard,
             ":search" => Command::StartSeach,
             ":stop" => Command::StopSearch,
+            ":go" => Command::Go(parse_go_command(&command)?),
             ":fen" if command.len() > 5 => Command::SetPosition(command[5..].to_string()),
+            ":setoption" if command.len() > 11 => Command::SetOption(command[11..].to_string()),
+            ":engine" => Command::Engine(parse_engine_command(&command)?),
             ":move" if command.len() > 6 => {
                 let mov = parse_algebraic_move(command[6..].to_string())?;
                 Command::MakeMove(mov)
@@ -161,6 +202,112 @@ impl Command {
     }
 }
 
+/// Parse the subcommands of `:engine`. i.e. `:engine load ./path/to/sf`
+/// Engines are numbered from 1 in commands and from 0 in the parsed values.
+fn parse_engine_command(command: &str) -> Result<EngineCommand> {
+    let mut words = command.split_whitespace().skip(1);
+    let cmd = match words.next() {
+        Some(sub @ ("load" | "add")) => {
+            let path = words.collect::<Vec<_>>().join(" ");
+            if path.is_empty() {
+                bail!(CommandError::InvalidCommand);
+            }
+            match sub {
+                "load" => EngineCommand::Load(path),
+                _ => EngineCommand::Add(path),
+            }
+        }
+        Some("restart") => EngineCommand::Restart(parse_engine_number(words.next())?),
+        Some("unload") => EngineCommand::Unload(parse_engine_number(words.next())?),
+        Some("enable") => EngineCommand::Enable(require_engine_number(words.next())?),
+        Some("disable") => EngineCommand::Disable(require_engine_number(words.next())?),
+        Some("select") => EngineCommand::Select(require_engine_number(words.next())?),
+        Some("list") | None => EngineCommand::List,
+        Some("autorestart") => EngineCommand::AutoRestart(parse_switch(words.next())?),
+        Some("logevals") => EngineCommand::LogEvals(parse_switch(words.next())?),
+        _ => bail!(CommandError::InvalidCommand),
+    };
+    Ok(cmd)
+}
+
+/// Parse the arguments of `:go`. i.e. `:go depth 20 searchmoves e2e4 d2d4`
+fn parse_go_command(command: &str) -> Result<SearchParams> {
+    let mut words = command.split_whitespace().skip(1);
+    let mut params = SearchParams::default();
+    while let Some(word) = words.next() {
+        let limit = match word {
+            "infinite" => SearchLimit::Infinite,
+            "depth" => SearchLimit::Depth(parse_go_value(word, words.next())?),
+            "movetime" => SearchLimit::MoveTime(parse_go_value(word, words.next())?),
+            "mate" => SearchLimit::Mate(parse_go_value(word, words.next())?),
+            "nodes" => SearchLimit::Nodes(parse_go_value(word, words.next())?),
+            "searchmoves" => {
+                params.searchmoves = words.by_ref().map(|w| w.to_string()).collect();
+                if params.searchmoves.is_empty() {
+                    bail!(CommandError::InvalidGoArgument {
+                        arg: word.to_string()
+                    });
+                }
+                continue;
+            }
+            _ => bail!(CommandError::InvalidGoArgument {
+                arg: word.to_string()
+            }),
+        };
+        params.limit = limit;
+    }
+    Ok(params)
+}
+
+/// Parse the arguments of `:analyze`, the limit of the search of each position.
+/// i.e. `:analyze depth 18` or `:analyze movetime 500`
+fn parse_analyze_command(command: &str) -> Result<Option<SearchLimit>> {
+    let mut words = command.split_whitespace().skip(1);
+    let limit = match words.next() {
+        None => None,
+        Some(word @ "depth") => Some(SearchLimit::Depth(parse_go_value(word, words.next())?)),
+        Some(word @ "movetime") => Some(SearchLimit::MoveTime(parse_go_value(word, words.next())?)),
+        Some(word) => bail!(CommandError::InvalidGoArgument {
+            arg: word.to_string()
+        }),
+    };
+    Ok(limit)
+}
+
+fn parse_go_value(arg: &str, value: Option<&str>) -> Result<usize> {
+    match value.map(|v| v.parse::<usize>()) {
+        Some(Ok(v)) if v > 0 => Ok(v),
+        _ => bail!(CommandError::InvalidGoArgument {
+            arg: arg.to_string()
+        }),
+    }
+}
+
+fn parse_switch(word: Option<&str>) -> Result<bool> {
+    match word {
+        Some("on") => Ok(true),
+        Some("off") => Ok(false),
+        _ => bail!(CommandError::InvalidCommand),
+    }
+}
+
+fn parse_engine_number(word: Option<&str>) -> Result<Option<usize>> {
+    match word {
+        Some(w) => match w.parse::<usize>() {
+            Ok(n) if n > 0 => Ok(Some(n - 1)),
+            _ => bail!(CommandError::InvalidEngineNumber { n: w.to_string() }),
+        },
+        None => Ok(None),
+    }
+}
+
+fn require_engine_number(word: Option<&str>) -> Result<usize> {
+    match parse_engine_number(word)? {
+        Some(n) => Ok(n),
+        None => bail!(CommandError::InvalidCommand),
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum ParsedMove {
     Basic { mov: Move },
@@ -183,7 +330,7 @@ fn parse_algebraic_move(mov: String) -> Result<ParsedMove> {
     })
 }
 
-fn parse_move_values(mov: &str) -> Result<(Position, Position)> {
+pub fn parse_move_values(mov: &str) -> Result<(Position, Position)> {
     let values = mov
         .chars()
         .take(4)
@@ -0,0 +1,975 @@
+use std::{
+    fmt::Display,
+    process::Stdio,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use async_uci::{
+    engine::{ChessEngine, EngineOption, Evaluation},
+    parse::{parse_uci, UCI},
+};
+use thiserror::Error;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    process::{Child, ChildStdin, ChildStdout, Command},
+    task::JoinHandle,
+    time::{sleep, timeout, Instant},
+};
+
+use crate::options::EngineOptions;
+
+/// Time given to the engine to answer to the handshake commands.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time given to the engine to exit after sending `quit` before killing it.
+const QUIT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Time between `isready` pings sent to check the engine is alive.
+const PING_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Time given to the engine to answer a ping before considering it hung.
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Time an engine has to run without failing for its automatic restarts to be forgotten.
+const STABLE_PERIOD: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Error)]
+pub enum EngineError {
+    #[error("engine didn't respond with {expected} in time")]
+    Timeout { expected: String },
+
+    #[error("engine process exited")]
+    ProcessExited,
+
+    #[error("engine is not responding")]
+    Unresponsive,
+
+    #[error("no engine loaded")]
+    NoEngineLoaded,
+
+    #[error("no engine with number {ix}")]
+    NoSuchEngine { ix: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EngineHealth {
+    Healthy,
+    Unresponsive,
+    Exited,
+}
+
+/// Condition for the engine to stop searching by itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SearchLimit {
+    #[default]
+    Infinite,
+    Depth(usize),
+    MoveTime(usize),
+    Mate(usize),
+    Nodes(usize),
+}
+
+/// Parameters of a `go` command.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SearchParams {
+    pub limit: SearchLimit,
+    /// Moves to restrict the search to, in long algebraic notation. Empty to search all.
+    pub searchmoves: Vec<String>,
+}
+
+impl SearchParams {
+    pub fn is_infinite(&self) -> bool {
+        self.limit == SearchLimit::Infinite
+    }
+}
+
+impl Display for SearchParams {
+    /// Arguments of the `go` command for these parameters, i.e. `depth 20 searchmoves e2e4`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.limit {
+            SearchLimit::Infinite => write!(f, "infinite")?,
+            SearchLimit::Depth(plies) => write!(f, "depth {}", plies)?,
+            SearchLimit::MoveTime(ms) => write!(f, "movetime {}", ms)?,
+            SearchLimit::Mate(moves) => write!(f, "mate {}", moves)?,
+            SearchLimit::Nodes(nodes) => write!(f, "nodes {}", nodes)?,
+        }
+        if !self.searchmoves.is_empty() {
+            write!(f, " searchmoves {}", self.searchmoves.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Move chosen by the engine at the end of a search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BestMove {
+    pub mov: String,
+    pub ponder: Option<String>,
+}
+
+impl Display for BestMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.ponder {
+            Some(ponder) => write!(f, "{} (ponder: {})", self.mov, ponder),
+            None => write!(f, "{}", self.mov),
+        }
+    }
+}
+
+/// Centipawns of advantage counted as drawish by the fallback win probability model.
+const WDL_DRAW_MARGIN: f64 = 100.0;
+
+/// Centipawns for the win probability to grow by a factor of e in the fallback model.
+const WDL_SCALE: f64 = 80.0;
+
+/// Win, draw and loss probabilities in permille, from the point of view of the side to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Wdl {
+    pub win: usize,
+    pub draw: usize,
+    pub loss: usize,
+}
+
+impl Wdl {
+    /// Estimate the probabilities from a centipawn score, for engines that don't report them.
+    /// This is a rough logistic model, equal positions are mostly drawn and the chances of
+    /// the side ahead grow quickly after a pawn of advantage.
+    pub fn from_score(cp: isize) -> Self {
+        let logistic = |x: f64| 1.0 / (1.0 + (-x).exp());
+        let cp = cp as f64;
+        let win = (logistic((cp - WDL_DRAW_MARGIN) / WDL_SCALE) * 1000.0).round() as usize;
+        let loss = (logistic((-cp - WDL_DRAW_MARGIN) / WDL_SCALE) * 1000.0).round() as usize;
+        Self {
+            win,
+            draw: 1000 - win - loss,
+            loss,
+        }
+    }
+
+    /// Certain result for a forced mate, positive when the side to move mates.
+    pub fn from_mate(mate: isize) -> Self {
+        match mate > 0 {
+            true => Self {
+                win: 1000,
+                draw: 0,
+                loss: 0,
+            },
+            false => Self {
+                win: 0,
+                draw: 0,
+                loss: 1000,
+            },
+        }
+    }
+
+    /// Expected result between 0 (loss) and 1 (win), counting draws as half a point.
+    pub fn expected_score(&self) -> f64 {
+        (self.win as f64 + self.draw as f64 / 2.0) / 1000.0
+    }
+
+    /// The same probabilities from the point of view of the other side.
+    pub fn flipped(self) -> Self {
+        Self {
+            win: self.loss,
+            draw: self.draw,
+            loss: self.win,
+        }
+    }
+}
+
+/// Search statistics reported in `info` lines that `Evaluation` doesn't keep, together
+/// with the time the search has been running.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SearchInfo {
+    pub nps: Option<usize>,
+    /// Hash table usage in permille.
+    pub hashfull: Option<usize>,
+    pub tbhits: Option<usize>,
+    pub currmove: Option<String>,
+    pub currmovenumber: Option<usize>,
+    /// Reported by engines with `UCI_ShowWDL` enabled.
+    pub wdl: Option<Wdl>,
+    started: Option<Instant>,
+    finished: Option<Instant>,
+}
+
+impl SearchInfo {
+    fn start() -> Self {
+        Self {
+            started: Some(Instant::now()),
+            ..Default::default()
+        }
+    }
+
+    /// Time since the search started, or its duration if it finished.
+    pub fn elapsed(&self) -> Option<Duration> {
+        let started = self.started?;
+        Some(self.finished.unwrap_or_else(Instant::now) - started)
+    }
+
+    /// Update the fields present in an `info` line, other lines are ignored.
+    fn update(&mut self, line: &str) {
+        let mut words = line.split_whitespace();
+        if words.next() != Some("info") {
+            return;
+        }
+        while let Some(word) = words.next() {
+            match word {
+                "nps" => self.nps = words.next().and_then(|w| w.parse().ok()),
+                "hashfull" => self.hashfull = words.next().and_then(|w| w.parse().ok()),
+                "tbhits" => self.tbhits = words.next().and_then(|w| w.parse().ok()),
+                "currmove" => self.currmove = words.next().map(|w| w.to_string()),
+                "currmovenumber" => self.currmovenumber = words.next().and_then(|w| w.parse().ok()),
+                "wdl" => {
+                    let mut next = || words.next().and_then(|w| w.parse().ok());
+                    if let (Some(win), Some(draw), Some(loss)) = (next(), next(), next()) {
+                        self.wdl = Some(Wdl { win, draw, loss });
+                    }
+                }
+                // the rest of the line are moves or free text
+                "pv" | "refutation" | "currline" | "string" => break,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Extends `ChessEngine` with the lifecycle management needed to swap engines at runtime.
+#[async_trait]
+pub trait ManagedEngine: ChessEngine + Send {
+    /// Name reported by the engine with `id name`.
+    fn name(&self) -> String;
+
+    /// Ask the engine to exit, killing the process if it doesn't exit in time.
+    async fn quit(&mut self) -> Result<()>;
+
+    /// Send an `isready` ping without waiting for the answer, see `health`.
+    async fn ping(&mut self) -> Result<()>;
+
+    /// Check if the process is alive and answered the last ping in time.
+    fn health(&mut self) -> EngineHealth;
+
+    /// Start a search with the given parameters.
+    async fn go(&mut self, params: &SearchParams) -> Result<()>;
+
+    /// Whether a search started with `go` hasn't finished yet.
+    fn is_searching(&self) -> bool;
+
+    /// Take the best move reported when the last search finished, if any.
+    fn take_bestmove(&mut self) -> Option<BestMove>;
+
+    /// Statistics of the current search, or of the last one if it finished.
+    fn search_info(&self) -> SearchInfo;
+}
+
+/// State shared between the engine handle and the task reading the engine output.
+#[derive(Default)]
+struct EngineState {
+    name: Option<String>,
+    uciok: bool,
+    readyok: bool,
+    exited: bool,
+    ping_sent: Option<Instant>,
+    /// Searches started and not finished yet. Stopping a search to start a new one leaves
+    /// two pending, and the `bestmove` of the first one must not end the second one. Info
+    /// lines are only read while a single search is pending.
+    pending_searches: usize,
+    bestmove: Option<BestMove>,
+    evaluation: Option<Evaluation>,
+    info: SearchInfo,
+    options: Vec<EngineOption>,
+}
+
+/// A UCI engine running in a child process.
+pub struct UciEngine {
+    proc: Child,
+    stdin: ChildStdin,
+    state: Arc<Mutex<EngineState>>,
+    reader: JoinHandle<()>,
+}
+
+impl UciEngine {
+    pub async fn new(exe_path: &str) -> Result<Self> {
+        let mut proc = Command::new(exe_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()?;
+        let stdout = proc.stdout.take().expect("no stdout available");
+        let stdin = proc.stdin.take().expect("no stdin available");
+        let state = Arc::new(Mutex::new(EngineState::default()));
+        let reader = tokio::spawn(process_stdout(BufReader::new(stdout), state.clone()));
+        Ok(UciEngine {
+            proc,
+            stdin,
+            state,
+            reader,
+        })
+    }
+
+    async fn send_command(&mut self, command: String) -> Result<()> {
+        if self.state.lock().expect("couldn't acquire lock").exited {
+            bail!(EngineError::ProcessExited);
+        }
+        self.stdin.write_all(command.as_bytes()).await?;
+        self.stdin.flush().await?;
+        Ok(())
+    }
+
+    /// Poll the engine state until `check` is true, the engine exits or the timeout expires.
+    async fn wait_for(
+        &mut self,
+        expected: &str,
+        check: impl Fn(&EngineState) -> bool,
+    ) -> Result<()> {
+        let start = Instant::now();
+        while start.elapsed() < HANDSHAKE_TIMEOUT {
+            {
+                let state = self.state.lock().expect("couldn't acquire lock");
+                if check(&state) {
+                    return Ok(());
+                }
+                if state.exited {
+                    bail!(EngineError::ProcessExited);
+                }
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+        bail!(EngineError::Timeout {
+            expected: expected.to_string()
+        })
+    }
+
+    async fn is_ready(&mut self) -> Result<()> {
+        self.state.lock().expect("couldn't acquire lock").readyok = false;
+        self.send_command("isready\n".to_string()).await?;
+        self.wait_for("readyok", |s| s.readyok).await
+    }
+}
+
+/// Read the engine output until the process closes it, updating the shared state.
+async fn process_stdout(mut stdout: BufReader<ChildStdout>, state: Arc<Mutex<EngineState>>) {
+    loop {
+        let mut line = String::new();
+        match stdout.read_line(&mut line).await {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        let mut state = state.lock().expect("couldn't acquire lock");
+        if let Some(name) = line.trim().strip_prefix("id name ") {
+            state.name = Some(name.to_string());
+            continue;
+        }
+        if let Some(bestmove) = parse_bestmove(&line) {
+            state.pending_searches = state.pending_searches.saturating_sub(1);
+            if state.pending_searches == 0 {
+                state.bestmove = Some(bestmove);
+                state.info.finished = Some(Instant::now());
+            }
+            continue;
+        }
+        // the output of a stopped search still reporting isn't about the current one
+        if line.starts_with("info") && state.pending_searches != 1 {
+            continue;
+        }
+        let parsed = parse_uci(line.clone());
+        // only the main line is shown, further lines of a multipv search are ignored
+        if matches!(parsed, Ok(UCI::Info { multipv: Some(n), .. }) if n > 1) {
+            continue;
+        }
+        state.info.update(&line);
+        match parsed {
+            Ok(UCI::UciOk) => state.uciok = true,
+            Ok(UCI::ReadyOk) => {
+                state.readyok = true;
+                state.ping_sent = None;
+            }
+            Ok(UCI::Info {
+                cp,
+                mate,
+                depth,
+                nodes,
+                seldepth,
+                time,
+                multipv,
+                pv,
+            }) => {
+                let prev = state.evaluation.take().unwrap_or_default();
+                // a line reports either a centipawn or a mate score, which replaces both
+                let (score, mate) = match (cp, mate) {
+                    (Some(cp), _) => (cp, 0),
+                    (None, Some(mate)) => (0, mate),
+                    (None, None) => (prev.score, prev.mate),
+                };
+                state.evaluation = Some(Evaluation {
+                    score,
+                    mate,
+                    depth: depth.unwrap_or(prev.depth),
+                    nodes: nodes.unwrap_or(prev.nodes),
+                    seldepth: seldepth.unwrap_or(prev.seldepth),
+                    multipv: multipv.unwrap_or(prev.multipv),
+                    pv: pv.unwrap_or(prev.pv),
+                    time: time.unwrap_or(prev.time),
+                });
+            }
+            Ok(UCI::Option { opt_type, .. }) => {
+                let name = parse_option_name(&line);
+                state.options.push(EngineOption { name, opt_type });
+            }
+            _ => {}
+        }
+    }
+    state.lock().expect("couldn't acquire lock").exited = true;
+}
+
+/// Get the full name of an option line, as names can contain spaces (i.e. `Clear Hash`).
+fn parse_option_name(line: &str) -> String {
+    let words = line.split_whitespace().collect::<Vec<_>>();
+    let start = words
+        .iter()
+        .position(|w| *w == "name")
+        .map_or(0, |ix| ix + 1);
+    let end = words
+        .iter()
+        .position(|w| *w == "type")
+        .unwrap_or(words.len());
+    words[start..end.max(start)].join(" ")
+}
+
+/// Parse a `bestmove <move> [ponder <move>]` line.
+fn parse_bestmove(line: &str) -> Option<BestMove> {
+    let mut words = line.split_whitespace();
+    if words.next() != Some("bestmove") {
+        return None;
+    }
+    let mov = words.next()?.to_string();
+    let ponder = match words.next() {
+        Some("ponder") => words.next().map(|w| w.to_string()),
+        _ => None,
+    };
+    Some(BestMove { mov, ponder })
+}
+
+#[async_trait]
+impl ChessEngine for UciEngine {
+    async fn start_uci(&mut self) -> Result<()> {
+        self.send_command("uci\n".to_string()).await?;
+        self.wait_for("uciok", |s| s.uciok).await?;
+        self.is_ready().await
+    }
+
+    async fn new_game(&mut self) -> Result<()> {
+        self.send_command("ucinewgame\n".to_string()).await?;
+        self.is_ready().await
+    }
+
+    async fn set_position(&mut self, fen: &str) -> Result<()> {
+        // the output of a running search would be taken for the new position, so it's
+        // stopped and its best move awaited first
+        if self.is_searching() {
+            self.send_command("stop\n".to_string()).await?;
+            self.wait_for("bestmove", |s| s.pending_searches == 0)
+                .await?;
+        }
+        self.send_command(format!("position fen {}\n", fen)).await?;
+        // the evaluation of the previous position is no longer meaningful
+        let mut state = self.state.lock().expect("couldn't acquire lock");
+        state.evaluation = None;
+        state.info = SearchInfo::default();
+        state.bestmove = None;
+        Ok(())
+    }
+
+    async fn go_infinite(&mut self) -> Result<()> {
+        self.send_command("go infinite\n".to_string()).await
+    }
+
+    async fn go_depth(&mut self, plies: usize) -> Result<()> {
+        self.send_command(format!("go depth {}\n", plies)).await
+    }
+
+    async fn go_time(&mut self, ms: usize) -> Result<()> {
+        self.send_command(format!("go movetime {}\n", ms)).await
+    }
+
+    async fn go_mate(&mut self, mate_in: usize) -> Result<()> {
+        self.send_command(format!("go mate {}\n", mate_in)).await
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        self.send_command("stop\n".to_string()).await
+    }
+
+    async fn get_evaluation(&mut self) -> Option<Evaluation> {
+        let state = self.state.lock().expect("couldn't acquire lock");
+        state.evaluation.clone()
+    }
+
+    async fn get_options(&mut self) -> Result<Vec<EngineOption>> {
+        let state = self.state.lock().expect("couldn't acquire lock");
+        Ok(state.options.clone())
+    }
+
+    async fn set_option(&mut self, option: String, value: String) -> Result<()> {
+        let cmd = match value.is_empty() {
+            true => format!("setoption name {}\n", option),
+            false => format!("setoption name {} value {}\n", option, value),
+        };
+        self.send_command(cmd).await
+    }
+}
+
+#[async_trait]
+impl ManagedEngine for UciEngine {
+    fn name(&self) -> String {
+        let state = self.state.lock().expect("couldn't acquire lock");
+        state
+            .name
+            .clone()
+            .unwrap_or_else(|| "unknown engine".to_string())
+    }
+
+    async fn quit(&mut self) -> Result<()> {
+        // the engine may already be gone, in which case there is nothing to wait for
+        let _ = self.send_command("quit\n".to_string()).await;
+        if timeout(QUIT_TIMEOUT, self.proc.wait()).await.is_err() {
+            self.proc.kill().await?;
+        }
+        self.reader.abort();
+        Ok(())
+    }
+
+    async fn ping(&mut self) -> Result<()> {
+        if self
+            .state
+            .lock()
+            .expect("couldn't acquire lock")
+            .ping_sent
+            .is_some()
+        {
+            return Ok(()); // still waiting for the previous one
+        }
+        self.send_command("isready\n".to_string()).await?;
+        self.state.lock().expect("couldn't acquire lock").ping_sent = Some(Instant::now());
+        Ok(())
+    }
+
+    fn health(&mut self) -> EngineHealth {
+        if let Ok(Some(_)) = self.proc.try_wait() {
+            return EngineHealth::Exited;
+        }
+        let state = self.state.lock().expect("couldn't acquire lock");
+        match state.ping_sent {
+            _ if state.exited => EngineHealth::Exited,
+            Some(sent) if sent.elapsed() > PING_TIMEOUT => EngineHealth::Unresponsive,
+            _ => EngineHealth::Healthy,
+        }
+    }
+
+    async fn go(&mut self, params: &SearchParams) -> Result<()> {
+        {
+            let mut state = self.state.lock().expect("couldn't acquire lock");
+            state.pending_searches += 1;
+            state.bestmove = None;
+            state.info = SearchInfo::start();
+        }
+        let res = match (params.limit, params.searchmoves.is_empty()) {
+            (SearchLimit::Infinite, true) => self.go_infinite().await,
+            (SearchLimit::Depth(plies), true) => self.go_depth(plies).await,
+            (SearchLimit::MoveTime(ms), true) => self.go_time(ms).await,
+            (SearchLimit::Mate(moves), true) => self.go_mate(moves).await,
+            _ => self.send_command(format!("go {}\n", params)).await,
+        };
+        if res.is_err() {
+            let mut state = self.state.lock().expect("couldn't acquire lock");
+            state.pending_searches = state.pending_searches.saturating_sub(1);
+        }
+        res
+    }
+
+    fn is_searching(&self) -> bool {
+        self.state
+            .lock()
+            .expect("couldn't acquire lock")
+            .pending_searches
+            > 0
+    }
+
+    fn take_bestmove(&mut self) -> Option<BestMove> {
+        self.state
+            .lock()
+            .expect("couldn't acquire lock")
+            .bestmove
+            .take()
+    }
+
+    fn search_info(&self) -> SearchInfo {
+        self.state
+            .lock()
+            .expect("couldn't acquire lock")
+            .info
+            .clone()
+    }
+}
+
+/// An engine that does nothing, used by default when the user does not provide an engine.
+pub struct NoopEngine {}
+
+#[async_trait]
+impl ChessEngine for NoopEngine {
+    async fn start_uci(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn new_game(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn set_position(&mut self, _position: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn go_infinite(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn go_depth(&mut self, _plies: usize) -> Result<()> {
+        Ok(())
+    }
+
+    async fn go_time(&mut self, _ms: usize) -> Result<()> {
+        Ok(())
+    }
+
+    async fn go_mate(&mut self, _mate_in: usize) -> Result<()> {
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn get_evaluation(&mut self) -> Option<Evaluation> {
+        None
+    }
+
+    async fn get_options(&mut self) -> Result<Vec<EngineOption>> {
+        Ok(Vec::new())
+    }
+
+    async fn set_option(&mut self, _option: String, _value: String) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ManagedEngine for NoopEngine {
+    fn name(&self) -> String {
+        "no engine".to_string()
+    }
+
+    async fn quit(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn ping(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn health(&mut self) -> EngineHealth {
+        EngineHealth::Healthy
+    }
+
+    async fn go(&mut self, _params: &SearchParams) -> Result<()> {
+        Ok(())
+    }
+
+    fn is_searching(&self) -> bool {
+        false
+    }
+
+    fn take_bestmove(&mut self) -> Option<BestMove> {
+        None
+    }
+
+    fn search_info(&self) -> SearchInfo {
+        SearchInfo::default()
+    }
+}
+
+/// An engine attached to the app, with the state kept for it by the UI.
+/// When no engine is loaded, or loading one fails, a `NoopEngine` is used instead.
+pub struct EngineSlot {
+    pub engine: Box<dyn ManagedEngine>,
+    pub path: Option<String>,
+    pub enabled: bool,
+    pub evaluation: Evaluation,
+    pub info: SearchInfo,
+    pub options: EngineOptions,
+    pub restarts: usize,
+    running: bool,
+    started: Instant,
+    last_ping: Instant,
+}
+
+impl EngineSlot {
+    pub fn new() -> Self {
+        Self {
+            engine: Box::new(NoopEngine {}),
+            path: None,
+            enabled: true,
+            evaluation: Evaluation::default(),
+            info: SearchInfo::default(),
+            options: EngineOptions::new(),
+            restarts: 0,
+            running: false,
+            started: Instant::now(),
+            last_ping: Instant::now(),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        self.engine.name()
+    }
+
+    /// Win, draw and loss probabilities for the side to move, the ones reported by the engine
+    /// or estimated from the score otherwise. `None` before the engine reported a score.
+    pub fn wdl(&self) -> Option<Wdl> {
+        if let Some(wdl) = self.info.wdl {
+            return Some(wdl);
+        }
+        match self.evaluation.mate {
+            _ if self.evaluation.depth == 0 => None,
+            0 => Some(Wdl::from_score(self.evaluation.score)),
+            mate => Some(Wdl::from_mate(mate)),
+        }
+    }
+
+    /// Whether an engine process is running, false when falling back to the `NoopEngine`.
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Whether the engine should be searching when the app is.
+    pub fn is_active(&self) -> bool {
+        self.running && self.enabled
+    }
+
+    /// Shutdown the current engine and start the one at `path`. If the engine fails to start
+    /// the path is kept, so it can be restarted later.
+    pub async fn load(&mut self, path: String) -> Result<()> {
+        self.shutdown().await?;
+        self.path = Some(path.clone());
+        let mut engine = UciEngine::new(path.as_str()).await?;
+        if let Err(err) = engine.start_uci().await {
+            engine.quit().await?;
+            return Err(err);
+        }
+        self.engine = Box::new(engine);
+        self.running = true;
+        self.started = Instant::now();
+        self.last_ping = Instant::now();
+        Ok(())
+    }
+
+    /// Shutdown the current engine and start it again.
+    pub async fn restart(&mut self) -> Result<()> {
+        match self.path.clone() {
+            Some(path) => self.load(path).await,
+            None => bail!(EngineError::NoEngineLoaded),
+        }
+    }
+
+    /// Shutdown the current engine and fallback to a `NoopEngine`, keeping the path of the
+    /// engine so it can be restarted.
+    pub async fn shutdown(&mut self) -> Result<()> {
+        let mut engine: Box<dyn ManagedEngine> = Box::new(NoopEngine {});
+        std::mem::swap(&mut self.engine, &mut engine);
+        self.running = false;
+        self.evaluation = Evaluation::default();
+        self.info = SearchInfo::default();
+        engine.quit().await
+    }
+
+    /// Ping the engine periodically, returning an error if it exited or stopped responding.
+    /// The restart counter is reset once the engine has been running for `STABLE_PERIOD`.
+    pub async fn check_health(&mut self) -> Result<()> {
+        match self.engine.health() {
+            EngineHealth::Exited => bail!(EngineError::ProcessExited),
+            EngineHealth::Unresponsive => bail!(EngineError::Unresponsive),
+            EngineHealth::Healthy => {}
+        }
+        if self.restarts > 0 && self.started.elapsed() >= STABLE_PERIOD {
+            self.restarts = 0;
+        }
+        if self.last_ping.elapsed() >= PING_INTERVAL {
+            self.last_ping = Instant::now();
+            self.engine.ping().await?;
+        }
+        Ok(())
+    }
+}
+
+/// Owns the engines attached to the app, allowing to load, restart and unload them at
+/// runtime. There is always at least one slot, which may hold a `NoopEngine`.
+pub struct EngineManager {
+    pub slots: Vec<EngineSlot>,
+    selected: usize,
+}
+
+impl EngineManager {
+    pub fn new() -> Self {
+        Self {
+            slots: vec![EngineSlot::new()],
+            selected: 0,
+        }
+    }
+
+    /// Index of the engine targeted by commands that don't specify one.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select(&mut self, ix: usize) -> Result<()> {
+        self.get_mut(ix)?;
+        self.selected = ix;
+        Ok(())
+    }
+
+    pub fn current(&self) -> &EngineSlot {
+        &self.slots[self.selected]
+    }
+
+    pub fn current_mut(&mut self) -> &mut EngineSlot {
+        &mut self.slots[self.selected]
+    }
+
+    pub fn get_mut(&mut self, ix: usize) -> Result<&mut EngineSlot> {
+        self.slots
+            .get_mut(ix)
+            .ok_or_else(|| EngineError::NoSuchEngine { ix: ix + 1 }.into())
+    }
+
+    /// Attach a new slot and return its index. The first slot is reused while it's empty.
+    pub fn add(&mut self) -> usize {
+        if self.slots.len() == 1 && self.slots[0].path.is_none() {
+            return 0;
+        }
+        self.slots.push(EngineSlot::new());
+        self.slots.len() - 1
+    }
+
+    /// Shutdown the engine and detach its slot, the last slot is kept empty instead.
+    pub async fn remove(&mut self, ix: usize) -> Result<()> {
+        let slot = self.get_mut(ix)?;
+        slot.path = None;
+        let res = slot.shutdown().await;
+        if self.slots.len() > 1 {
+            self.slots.remove(ix);
+            if self.selected >= ix && self.selected > 0 {
+                self.selected -= 1;
+            }
+        } else {
+            self.slots[0] = EngineSlot::new();
+        }
+        res
+    }
+
+    /// Shutdown all the engines.
+    pub async fn shutdown(&mut self) -> Result<()> {
+        for slot in self.slots.iter_mut() {
+            slot.shutdown().await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::engine::{
+        parse_bestmove, parse_option_name, BestMove, SearchInfo, SearchLimit, SearchParams, Wdl,
+    };
+
+    #[test]
+    fn test_parse_bestmove() {
+        assert_eq!(
+            parse_bestmove("bestmove e2e4 ponder e7e5\n"),
+            Some(BestMove {
+                mov: "e2e4".to_string(),
+                ponder: Some("e7e5".to_string())
+            })
+        );
+        assert_eq!(
+            parse_bestmove("bestmove a7a8q"),
+            Some(BestMove {
+                mov: "a7a8q".to_string(),
+                ponder: None
+            })
+        );
+        assert_eq!(parse_bestmove("info depth 1"), None);
+    }
+
+    #[test]
+    fn test_search_info_update() {
+        let mut info = SearchInfo::default();
+        info.update(
+            "info depth 12 seldepth 18 nodes 1200 nps 850000 hashfull 45 tbhits 3 pv e2e4 nps",
+        );
+        info.update("info depth 12 currmove g1f3 currmovenumber 4 wdl 400 500 100");
+        assert_eq!(info.nps, Some(850000));
+        assert_eq!(info.hashfull, Some(45));
+        assert_eq!(info.tbhits, Some(3));
+        assert_eq!(info.currmove, Some("g1f3".to_string()));
+        assert_eq!(info.currmovenumber, Some(4));
+        assert_eq!(
+            info.wdl,
+            Some(Wdl {
+                win: 400,
+                draw: 500,
+                loss: 100
+            })
+        );
+    }
+
+    #[test]
+    fn test_wdl_from_score() {
+        let equal = Wdl::from_score(0);
+        assert_eq!(equal.win, equal.loss);
+        assert!(equal.draw > equal.win);
+        assert_eq!(Wdl::from_score(-250), Wdl::from_score(250).flipped());
+        let winning = Wdl::from_score(1000);
+        assert!(winning.win > 990);
+        assert_eq!(winning.win + winning.draw + winning.loss, 1000);
+    }
+
+    #[test]
+    fn test_search_params() {
+        let params = SearchParams {
+            limit: SearchLimit::Nodes(1000),
+            searchmoves: vec!["e2e4".to_string(), "d2d4".to_string()],
+        };
+        assert_eq!(params.to_string(), "nodes 1000 searchmoves e2e4 d2d4");
+    }
+
+    #[test]
+    fn test_parse_option_name() {
+        let cases = [
+            (
+                "option name Hash type spin default 16 min 1 max 33554432",
+                "Hash",
+            ),
+            ("option name Clear Hash type button", "Clear Hash"),
+            (
+                "option name Skill Level type spin default 20 min 0 max 20",
+                "Skill Level",
+            ),
+        ];
+        for (line, name) in cases {
+            assert_eq!(parse_option_name(line), name);
+        }
+    }
+}
@@ -0,0 +1,592 @@
+use anyhow::Result;
+
+use crate::{
+    app::INITIAL_POSITION,
+    board::{Board, Move},
+    engine::SearchParams,
+    fen::Fen,
+};
+
+/// Longest PGN line, as recommended by the standard.
+const PGN_LINE_LENGTH: usize = 79;
+
+/// Evaluation of a position from white's point of view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlyEval {
+    /// Centipawns, positive when white is better.
+    pub score: isize,
+    /// Moves to mate, positive when white mates. 0 when there is no forced mate.
+    pub mate: isize,
+    pub depth: isize,
+}
+
+impl PlyEval {
+    /// Score in pawns clamped to `limit`, mates count as the limit.
+    pub fn pawns(&self, limit: f64) -> f64 {
+        match self.mate {
+            0 => (self.score as f64 / 100.0).clamp(-limit, limit),
+            m if m > 0 => limit,
+            _ => -limit,
+        }
+    }
+
+    /// Chances of white between -1 (lost) and 1 (won), using the same model as lichess to
+    /// translate centipawns.
+    pub fn winning_chances(&self) -> f64 {
+        match self.mate {
+            0 => 2.0 / (1.0 + (-0.00368208 * self.score as f64).exp()) - 1.0,
+            m if m > 0 => 1.0,
+            _ => -1.0,
+        }
+    }
+
+    /// Evaluation of a position without legal moves, which needs no search: a mate when the
+    /// side to move is in check, a draw otherwise. `None` while there are legal moves.
+    pub fn terminal(board: &Board) -> Option<Self> {
+        if !board.get_legal_moves().is_empty() {
+            return None;
+        }
+        let mate = match (board.in_check(), board.white_to_move()) {
+            (false, _) => 0,
+            (true, true) => -1,
+            (true, false) => 1,
+        };
+        Some(Self {
+            score: 0,
+            mate,
+            depth: 0,
+        })
+    }
+
+    /// The evaluation as used in PGN `[%eval]` comments, i.e. `0.25` or `#-3`.
+    pub fn pgn_eval(&self) -> String {
+        match self.mate {
+            0 => format!("{:.2}", self.score as f64 / 100.0),
+            m => format!("#{}", m),
+        }
+    }
+}
+
+/// Quality of a move, judged by how much it lowered the chances of the side that played it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Judgement {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Judgement {
+    /// Judge a move by the drop of the winning chances of the player, from -1 to 1.
+    fn from_drop(drop: f64) -> Option<Self> {
+        match drop {
+            d if d >= 0.3 => Some(Judgement::Blunder),
+            d if d >= 0.2 => Some(Judgement::Mistake),
+            d if d >= 0.1 => Some(Judgement::Inaccuracy),
+            _ => None,
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Judgement::Inaccuracy => "?!",
+            Judgement::Mistake => "?",
+            Judgement::Blunder => "??",
+        }
+    }
+
+    /// Numeric Annotation Glyph used in PGN.
+    pub fn nag(&self) -> &'static str {
+        match self {
+            Judgement::Inaccuracy => "$6",
+            Judgement::Mistake => "$2",
+            Judgement::Blunder => "$4",
+        }
+    }
+}
+
+/// Progress of a full game analysis, the positions of the game are searched one at a time.
+pub struct GameAnalysis {
+    pub params: SearchParams,
+    /// Ply being searched.
+    pub ply: usize,
+    /// Whether white is to move in the position being searched.
+    pub white_to_move: bool,
+}
+
+impl GameAnalysis {
+    pub fn new(params: SearchParams) -> Self {
+        Self {
+            params,
+            ply: 0,
+            white_to_move: true,
+        }
+    }
+}
+
+/// A line branching off the game, played instead of the move after `ply`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variation {
+    /// Moves of the game played before the line.
+    pub ply: usize,
+    pub moves: Vec<Move>,
+}
+
+/// How a line was added to the game by `GameRecord::add_line`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddedLine {
+    /// The game already has the line, in its moves or in a variation.
+    Known,
+    /// The line follows the game to its last move and continues it.
+    Continuation,
+    Variation,
+}
+
+/// Moves of the game from its starting position, together with the evaluations recorded for
+/// each ply. Ply 0 is the starting position. The board may be showing an earlier ply, the
+/// following moves are kept until a different move is played.
+pub struct GameRecord {
+    start_fen: String,
+    white_starts: bool,
+    moves: Vec<Move>,
+    evals: Vec<Option<PlyEval>>,
+    variations: Vec<Variation>,
+    /// Plies after which the side to move passed its turn, in order.
+    passes: Vec<usize>,
+}
+
+impl GameRecord {
+    pub fn new(start_fen: String) -> Self {
+        let white_starts = Fen::parse(start_fen.clone()).map_or(true, |f| f.white_to_move);
+        Self {
+            start_fen,
+            white_starts,
+            moves: Vec::new(),
+            evals: vec![None],
+            variations: Vec::new(),
+            passes: Vec::new(),
+        }
+    }
+
+    /// Start a new game from the given position, dropping the moves and evaluations.
+    pub fn reset(&mut self, start_fen: String) {
+        *self = Self::new(start_fen);
+    }
+
+    /// Whether white made the first move of the game.
+    pub fn white_starts(&self) -> bool {
+        self.white_starts
+    }
+
+    /// Number of plies in the game.
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn evals(&self) -> &[Option<PlyEval>] {
+        &self.evals
+    }
+
+    /// Variations branching off the game, in the order of the moves they replace.
+    pub fn variations(&self) -> &[Variation] {
+        &self.variations
+    }
+
+    /// Add a line played from the position after `ply` moves of the game. The moves it shares
+    /// with the game are skipped and the rest is kept as a variation, unless the line follows
+    /// the game to its last move, which is then continued.
+    pub fn add_line(&mut self, ply: usize, line: &[Move]) -> AddedLine {
+        let shared = self.moves[ply.min(self.moves.len())..]
+            .iter()
+            .zip(line)
+            .take_while(|(a, b)| a == b)
+            .count();
+        let (ply, line) = (ply + shared, &line[shared..]);
+        if line.is_empty() {
+            return AddedLine::Known;
+        }
+        if ply >= self.moves.len() {
+            self.moves.extend_from_slice(line);
+            self.evals.resize(self.moves.len() + 1, None);
+            return AddedLine::Continuation;
+        }
+        let variation = Variation {
+            ply,
+            moves: line.to_vec(),
+        };
+        if self.variations.contains(&variation) {
+            return AddedLine::Known;
+        }
+        let ix = self.variations.partition_point(|v| v.ply <= ply);
+        self.variations.insert(ix, variation);
+        AddedLine::Variation
+    }
+
+    /// Plies after which the side to move passed its turn.
+    pub fn passes(&self) -> &[usize] {
+        &self.passes
+    }
+
+    /// Pass the turn after `ply` moves, or take back a pass made there. The moves following
+    /// it were played by the other side, so they are dropped together with their variations.
+    pub fn pass(&mut self, ply: usize) {
+        self.moves.truncate(ply);
+        self.variations.retain(|v| v.ply < ply);
+        self.evals.truncate(ply);
+        self.evals.push(None);
+        match self.passes.binary_search(&ply) {
+            Ok(ix) => {
+                self.passes.remove(ix);
+            }
+            Err(ix) => self.passes.insert(ix, ply),
+        }
+    }
+
+    /// Update the game with the moves played on the board. The moves after the ones played
+    /// are kept if they follow the same line, and dropped otherwise.
+    pub fn sync(&mut self, history: &[Move]) {
+        if self.moves.starts_with(history) {
+            return;
+        }
+        let common = self.common_plies(history);
+        self.moves = history.to_vec();
+        // the variations of the dropped moves branch off positions no longer in the game
+        self.variations.retain(|v| v.ply <= common);
+        self.passes.retain(|&p| p <= common);
+        self.evals.truncate(common + 1);
+        self.evals.resize(history.len() + 1, None);
+    }
+
+    /// Number of moves the game and the history have in common from the start.
+    fn common_plies(&self, history: &[Move]) -> usize {
+        self.moves
+            .iter()
+            .zip(history)
+            .take_while(|(a, b)| a == b)
+            .count()
+    }
+
+    /// Record the evaluation of the position after `ply` moves, keeping the deepest one.
+    pub fn record(&mut self, ply: usize, eval: PlyEval) {
+        if let Some(slot) = self.evals.get_mut(ply) {
+            match slot {
+                Some(prev) if prev.depth > eval.depth => {}
+                _ => *slot = Some(eval),
+            }
+        }
+    }
+
+    /// Judge the move that lead to the position after `ply` moves, if both the position
+    /// before and after it were evaluated.
+    pub fn judgement(&self, ply: usize) -> Option<Judgement> {
+        let before = self.evals.get(ply.checked_sub(1)?)?.as_ref()?;
+        let after = self.evals.get(ply)?.as_ref()?;
+        let drop = before.winning_chances() - after.winning_chances();
+        match self.white_moves(ply - 1) {
+            true => Judgement::from_drop(drop),
+            false => Judgement::from_drop(-drop),
+        }
+    }
+
+    /// Number of turns played before the move at index `ix`, counting the passes.
+    fn half_moves(&self, ix: usize) -> usize {
+        ix + self.passes.iter().filter(|&&p| p <= ix).count()
+    }
+
+    /// Whether white plays the move at index `ix`.
+    fn white_moves(&self, ix: usize) -> bool {
+        self.half_moves(ix).is_multiple_of(2) == self.white_starts
+    }
+
+    /// Export the game as PGN, with the evaluations as `[%eval]` comments, the judgement
+    /// of the moves as NAGs and the passes as `--` null moves.
+    pub fn to_pgn(&self) -> Result<String> {
+        let result = self.result()?;
+        let mut pgn = String::new();
+        let headers = [
+            ("Event", "?"),
+            ("Site", "?"),
+            ("Date", "????.??.??"),
+            ("Round", "?"),
+            ("White", "?"),
+            ("Black", "?"),
+            ("Result", result),
+        ];
+        for (name, value) in headers {
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+        }
+        if self.start_fen != INITIAL_POSITION {
+            pgn.push_str("[SetUp \"1\"]\n");
+            pgn.push_str(&format!("[FEN \"{}\"]\n", self.board_at(0)?.as_fen()));
+        }
+        pgn.push('\n');
+
+        let mut tokens = Vec::new();
+        let mut board = self.board_at(0)?;
+        let mut after_variation = false;
+        for (ix, mov) in self.moves.iter().enumerate() {
+            let ply = ix + 1;
+            if self.passes.contains(&ix) {
+                let half = self.half_moves(ix) - 1;
+                tokens.extend(self.move_number(half, board.white_to_move(), after_variation));
+                tokens.push("--".to_string());
+                board.pass_turn();
+                after_variation = false;
+            }
+            let half = self.half_moves(ix);
+            if let Some(number) = self.move_number(half, board.white_to_move(), after_variation) {
+                tokens.push(number);
+            }
+            tokens.push(board.san(mov));
+            if let Some(judgement) = self.judgement(ply) {
+                tokens.push(judgement.nag().to_string());
+            }
+            // positions without legal moves aren't searched, the result tells their outcome
+            if let Some(eval) = self.evals[ply].filter(|e| e.depth > 0) {
+                tokens.push(format!("{{ [%eval {}] }}", eval.pgn_eval()));
+            }
+            after_variation = false;
+            for variation in self.variations.iter().filter(|v| v.ply == ix) {
+                let mut line_tokens = Vec::new();
+                let mut line = board.clone();
+                for (offset, mov) in variation.moves.iter().enumerate() {
+                    let first = offset == 0;
+                    if let Some(number) =
+                        self.move_number(half + offset, line.white_to_move(), first)
+                    {
+                        line_tokens.push(number);
+                    }
+                    line_tokens.push(line.san(mov));
+                    line.make_move(*mov)?;
+                }
+                tokens.push(format!("({})", line_tokens.join(" ")));
+                after_variation = true;
+            }
+            board.make_move(*mov)?;
+        }
+        if self.passes.contains(&self.moves.len()) {
+            let half = self.half_moves(self.moves.len()) - 1;
+            tokens.extend(self.move_number(half, board.white_to_move(), after_variation));
+            tokens.push("--".to_string());
+        }
+        tokens.push(result.to_string());
+
+        let mut line = String::new();
+        for token in tokens {
+            if !line.is_empty() && line.len() + token.len() + 1 > PGN_LINE_LENGTH {
+                pgn.push_str(&line);
+                pgn.push('\n');
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&token);
+        }
+        pgn.push_str(&line);
+        pgn.push('\n');
+        Ok(pgn)
+    }
+
+    /// Number of the move after `half` turns as written in PGN before it, `1.` for white and
+    /// `1...` for black. Black moves are only numbered when they start a line of moves.
+    fn move_number(&self, half: usize, white_moves: bool, starts_line: bool) -> Option<String> {
+        let number = (half + usize::from(!self.white_starts)) / 2 + 1;
+        match white_moves {
+            true => Some(format!("{}.", number)),
+            false if starts_line || half == 0 => Some(format!("{}...", number)),
+            false => None,
+        }
+    }
+
+    /// Result of the game as written in PGN, `*` unless it ended in mate or stalemate.
+    pub fn result(&self) -> Result<&'static str> {
+        let board = self.board_at(self.len())?;
+        let result = match PlyEval::terminal(&board) {
+            Some(eval) if eval.mate > 0 => "1-0",
+            Some(eval) if eval.mate < 0 => "0-1",
+            Some(_) => "1/2-1/2",
+            None => "*",
+        };
+        Ok(result)
+    }
+
+    /// A board with the position after the first `ply` moves of the game, and the passes
+    /// made until then.
+    pub fn board_at(&self, ply: usize) -> Result<Board> {
+        let mut board = Board::from_fen(self.start_fen.clone())?;
+        for (ix, mov) in self.moves.iter().take(ply).enumerate() {
+            if self.passes.contains(&ix) {
+                board.pass_turn();
+            }
+            board.make_move(*mov)?;
+        }
+        if self.passes.contains(&ply) {
+            board.pass_turn();
+        }
+        Ok(board)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        app::INITIAL_POSITION,
+        board::{Board, Move},
+        game::{AddedLine, GameRecord, Judgement, PlyEval},
+    };
+
+    fn eval(score: isize, depth: isize) -> PlyEval {
+        PlyEval {
+            score,
+            mate: 0,
+            depth,
+        }
+    }
+
+    #[test]
+    fn test_sync_keeps_evals_of_the_same_line() {
+        let mut game = GameRecord::new(INITIAL_POSITION.to_string());
+        let mut board = Board::from_fen(INITIAL_POSITION.to_string()).unwrap();
+        for uci in ["e2e4", "e7e5", "g1f3"] {
+            board
+                .make_move(board.find_legal_move(uci).unwrap())
+                .unwrap();
+            game.sync(&board.get_history());
+            game.record(game.len(), eval(30, 10));
+        }
+        assert_eq!(game.len(), 3);
+
+        // going back keeps the rest of the game
+        let mut board = game.board_at(1).unwrap();
+        game.sync(&board.get_history());
+        assert_eq!(game.len(), 3);
+
+        // a different move drops the moves and evals after it
+        board
+            .make_move(board.find_legal_move("c7c5").unwrap())
+            .unwrap();
+        game.sync(&board.get_history());
+        assert_eq!(game.len(), 2);
+        assert_eq!(game.evals().len(), 3);
+        assert!(game.evals()[1].is_some());
+        assert!(game.evals()[2].is_none());
+    }
+
+    #[test]
+    fn test_record_keeps_deepest() {
+        let mut game = GameRecord::new(INITIAL_POSITION.to_string());
+        game.record(0, eval(20, 12));
+        game.record(0, eval(50, 8));
+        assert_eq!(game.evals()[0], Some(eval(20, 12)));
+        game.record(0, eval(35, 14));
+        assert_eq!(game.evals()[0], Some(eval(35, 14)));
+    }
+
+    #[test]
+    fn test_judgement_and_pgn() {
+        let mut game = GameRecord::new(INITIAL_POSITION.to_string());
+        let mut board = Board::from_fen(INITIAL_POSITION.to_string()).unwrap();
+        for uci in ["e2e4", "e7e5", "d1h5"] {
+            board
+                .make_move(board.find_legal_move(uci).unwrap())
+                .unwrap();
+        }
+        game.sync(&board.get_history());
+        game.record(0, eval(20, 10));
+        game.record(1, eval(30, 10));
+        game.record(2, eval(400, 10));
+        game.record(3, eval(350, 10));
+
+        assert_eq!(game.judgement(1), None);
+        assert_eq!(game.judgement(2), Some(Judgement::Blunder));
+        assert_eq!(game.judgement(3), None);
+
+        let pgn = game.to_pgn().unwrap();
+        assert!(pgn.contains("[Result \"*\"]"));
+        assert!(pgn.ends_with(
+            "1. e4 { [%eval 0.30] } e5 $4 { [%eval 4.00] } 2. Qh5 { [%eval 3.50] } *\n"
+        ));
+    }
+
+    #[test]
+    fn test_terminal_eval_and_result() {
+        let mut game = GameRecord::new(INITIAL_POSITION.to_string());
+        let mut board = Board::from_fen(INITIAL_POSITION.to_string()).unwrap();
+        assert_eq!(PlyEval::terminal(&board), None);
+        for uci in ["f2f3", "e7e5", "g2g4", "d8h4"] {
+            board
+                .make_move(board.find_legal_move(uci).unwrap())
+                .unwrap();
+        }
+        game.sync(&board.get_history());
+        assert_eq!(PlyEval::terminal(&board).map(|e| e.mate), Some(-1));
+        assert_eq!(game.result().unwrap(), "0-1");
+
+        let pgn = game.to_pgn().unwrap();
+        assert!(pgn.contains("[Result \"0-1\"]"));
+        assert!(pgn.ends_with("2. g4 Qh4# 0-1\n"));
+
+        let stalemate = Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - -".to_string()).unwrap();
+        assert_eq!(PlyEval::terminal(&stalemate).map(|e| e.mate), Some(0));
+    }
+
+    /// Play the moves from the position after `ply` moves of the game.
+    fn play(game: &GameRecord, ply: usize, uci: &[&str]) -> Vec<Move> {
+        let mut board = game.board_at(ply).unwrap();
+        uci.iter()
+            .map(|m| {
+                let mov = board.find_legal_move(m).unwrap();
+                board.make_move(mov).unwrap();
+                mov
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_add_line() {
+        let mut game = GameRecord::new(INITIAL_POSITION.to_string());
+        let main = play(&game, 0, &["e2e4", "e7e5", "g1f3"]);
+        game.sync(&main);
+        let sicilian = play(&game, 1, &["c7c5", "g1f3"]);
+
+        assert_eq!(game.add_line(1, &main[1..]), AddedLine::Known);
+        assert_eq!(game.add_line(1, &sicilian), AddedLine::Variation);
+        assert_eq!(game.add_line(1, &sicilian), AddedLine::Known);
+        assert_eq!(game.len(), 3);
+        assert!(game
+            .to_pgn()
+            .unwrap()
+            .ends_with("1. e4 e5 (1... c5 2. Nf3) 2. Nf3 *\n"));
+
+        // a line following the game past its end continues it
+        let continuation = play(&game, 2, &["g1f3", "b8c6"]);
+        assert_eq!(game.add_line(2, &continuation), AddedLine::Continuation);
+        assert_eq!(game.len(), 4);
+        assert_eq!(game.evals().len(), 5);
+
+        // another first move drops the variations of the moves after it
+        game.sync(&[]);
+        assert_eq!(game.variations().len(), 1);
+        game.sync(&play(&game, 0, &["d2d4"]));
+        assert!(game.variations().is_empty());
+    }
+
+    #[test]
+    fn test_pass() {
+        let mut game = GameRecord::new(INITIAL_POSITION.to_string());
+        game.sync(&play(&game, 0, &["e2e4"]));
+        game.pass(1);
+        let mut history = game.moves.clone();
+        history.extend(play(&game, 1, &["d2d4", "e7e5"]));
+        game.sync(&history);
+        assert!(game.board_at(1).unwrap().white_to_move());
+        assert!(game.to_pgn().unwrap().ends_with("1. e4 -- 2. d4 e5 *\n"));
+
+        // passing earlier drops the moves played after it
+        game.pass(1);
+        assert_eq!(game.len(), 1);
+        assert!(game.passes().is_empty());
+        game.pass(1);
+        assert!(game.to_pgn().unwrap().ends_with("1. e4 -- *\n"));
+    }
+}
@@ -42,11 +42,58 @@ fn get_help() -> Vec<String> {
     let shortcuts = [
         ("<TAB>", "Next window"),
         ("<UP/DOWN> or k/j or MouseWheel", "Scroll"),
+        (
+            "<ARROWS> or h/j/k/l",
+            "Move the board cursor, while no pane is open beside the board",
+        ),
+        (
+            "<ENTER> or <SPACE>",
+            "Grab the piece under the board cursor, or drop the grabbed one",
+        ),
+        (
+            "<ESC>",
+            "Release the grabbed piece, or hide the board cursor",
+        ),
+        (
+            "a-g N R Q K O",
+            "Type a move in SAN or UCI on the Board tab, <ENTER> plays it",
+        ),
+        ("/", "Type a move starting with h or B"),
         ("F2", "Toggle mouse capture"),
+        (
+            "MouseClick",
+            "Switch tabs, select history and legal moves entries, move pieces",
+        ),
+        (
+            "MouseDrag",
+            "Drag a piece to its square, drop it off the board or right click to cancel",
+        ),
         (":", "Enter console and buffer with :"),
         ("!", "Enter console and buffer with !"),
         ("M", "Open legal moves pane"),
         ("H", "Open move history pane"),
+        (
+            "B",
+            "Cycle best move highlight: best move, best move and reply, off",
+        ),
+        ("P", "Open the PV explorer with the lines of the engines"),
+        ("E", "Open the evaluation graph of the game"),
+        (
+            "T",
+            "Toggle threat mode: shade the squares attacked by the opponent and show its threat",
+        ),
+        (
+            "X",
+            "Toggle marking pieces en prise: attacked more than defended or by a cheaper piece",
+        ),
+        (
+            "C",
+            "Toggle the control heatmap: blue squares are controlled by white, red by black",
+        ),
+        (
+            "L",
+            "Cycle rank and file labels: on the borders, inside the squares, hidden",
+        ),
         ("S", "Set starting position on the board"),
         ("q", "Quit"),
     ];
@@ -57,22 +104,103 @@ fn get_help() -> Vec<String> {
         ("<UP/DOWN>", "Traverse command history"),
         ("!fen", "Print current position as FEN in the console (use F2 to toggle mouse capture and copy it)"),
         (":fen <fen>", "Set position on the board"),
+        ("!pgn", "Print the game as PGN in the console, with evaluations and judgements"),
+        (":exportpgn <path>", "Save the game as PGN to a file"),
         (
             ":move <mv>",
             "Play move on the board. Long algebraic notation used (i.e. e2e4)",
         ),
         (":search", "Start searching for best move"),
+        (
+            ":go <depth|movetime|mate|nodes> <n>",
+            "Search with a limit, reporting the best move when done",
+        ),
+        (":go ... searchmoves <mv>...", "Restrict the search to the given moves"),
+        (
+            ":analyze [depth <n>|movetime <ms>]",
+            "Search every position of the game and judge the moves (?!, ?, ??)",
+        ),
         (":stop", "Stop searching for best move"),
         (":flipboard", "Flip board vertically"),
+        (":theme [name]", "List the board themes or switch to one"),
+        (":pieces [name]", "List the piece sets or switch to one"),
         (":passturn", "Pass current player turn"),
+        (":engine", "List the attached engines, * marks the selected one"),
+        (
+            ":engine load <path>",
+            "Start the engine at path, replacing the selected one",
+        ),
+        (":engine add <path>", "Start the engine at path alongside the others"),
+        (":engine restart [n]", "Restart the selected engine, or engine n"),
+        (":engine unload [n]", "Stop and detach the selected engine, or engine n"),
+        (":engine enable <n>", "Make engine n search with the others"),
+        (":engine disable <n>", "Stop engine n from searching"),
+        (
+            ":engine select <n>",
+            "Select engine n for commands and the Engine window",
+        ),
+        (
+            ":engine autorestart <on|off>",
+            "Restart the engine automatically if it crashes or hangs",
+        ),
+        (
+            ":engine logevals <on|off>",
+            "Log every evaluation update in the console",
+        ),
+        (
+            ":setoption <name> <value>",
+            "Set an engine option, i.e. Skill Level 5 (omit the value for buttons)",
+        ),
         (":q", "Quit"),
     ];
     let legal_moves_shortcuts = [
         ("M", "Close legal moves pane"),
-        ("<UP/DOWN> or k/j", "Change selected move"),
-        ("(wip) <RIGHT/LEFT> or l/h", "Open or close group"),
-        ("(wip) <ENTER>", "Make move on the board"),
-        ("(wip) G", "Toggle move grouping"),
+        (
+            "<UP/DOWN> or k/j",
+            "Change selected move, highlighted on the board",
+        ),
+        ("<RIGHT/LEFT> or l/h", "Open or close group"),
+        ("<ENTER>", "Make move on the board"),
+        (
+            "G",
+            "Cycle move grouping: by piece, checks/captures/quiet, none",
+        ),
+    ];
+    let pv_explorer_shortcuts = [
+        ("P", "Close the PV explorer"),
+        (
+            "<RIGHT/LEFT> or l/h",
+            "Step forwards/backwards through the line",
+        ),
+        ("<UP/DOWN> or k/j", "Select the line of another engine"),
+        (
+            "<ENTER>",
+            "Add the line up to the previewed position to the game as a variation",
+        ),
+    ];
+    let eval_graph_shortcuts = [
+        ("E", "Close the evaluation graph"),
+        (
+            "<RIGHT/LEFT> or l/h",
+            "Show the next/previous position of the game",
+        ),
+        (
+            "Click",
+            "Show the position at the clicked point of the graph",
+        ),
+    ];
+    let engine_shortcuts = [
+        ("<UP/DOWN> or k/j", "Change selected option"),
+        (
+            "<LEFT/RIGHT> or h/l",
+            "Decrease/increase spin, cycle combo or toggle check",
+        ),
+        (
+            "<ENTER>",
+            "Edit spin or string, toggle check, cycle combo or press button",
+        ),
+        ("<ENTER> (editing)", "Set edited value in the engine"),
+        ("<ESC> (editing)", "Cancel edition"),
     ];
     let shortcuts_help: Vec<Spans> = shortcuts
         .iter()
@@ -104,6 +232,36 @@ fn get_help() -> Vec<String> {
             ])
         })
         .collect();
+    let pv_explorer_shortcuts_help: Vec<Spans> = pv_explorer_shortcuts
+        .iter()
+        .map(|(k, v)| {
+            Spans::from(vec![
+                Span::styled(format!("  {}", k), Style::default().fg(Color::Yellow)),
+                Span::raw(" - "),
+                Span::raw(v.to_owned()),
+            ])
+        })
+        .collect();
+    let eval_graph_shortcuts_help: Vec<Spans> = eval_graph_shortcuts
+        .iter()
+        .map(|(k, v)| {
+            Spans::from(vec![
+                Span::styled(format!("  {}", k), Style::default().fg(Color::Yellow)),
+                Span::raw(" - "),
+                Span::raw(v.to_owned()),
+            ])
+        })
+        .collect();
+    let engine_shortcuts_help: Vec<Spans> = engine_shortcuts
+        .iter()
+        .map(|(k, v)| {
+            Spans::from(vec![
+                Span::styled(format!("  {}", k), Style::default().fg(Color::Yellow)),
+                Span::raw(" - "),
+                Span::raw(v.to_owned()),
+            ])
+        })
+        .collect();
     let mut text = Vec::new();
     text.extend(iter::once(Spans::from(
         "<< Scroll with UP/DOWN/j/k/MouseWheel >>",
@@ -117,5 +275,14 @@ fn get_help() -> Vec<String> {
     text.extend(iter::once(Spans::from("")));
     text.extend(iter::once(Spans::from("Legal Moves:")));
     text.extend(legal_moves_shortcuts_help);
+    text.extend(iter::once(Spans::from("")));
+    text.extend(iter::once(Spans::from("PV Explorer:")));
+    text.extend(pv_explorer_shortcuts_help);
+    text.extend(iter::once(Spans::from("")));
+    text.extend(iter::once(Spans::from("Evaluation Graph:")));
+    text.extend(eval_graph_shortcuts_help);
+    text.extend(iter::once(Spans::from("")));
+    text.extend(iter::once(Spans::from("Engine:")));
+    text.extend(engine_shortcuts_help);
     text.iter().map(|s| s.clone().into()).collect()
 }
@@ -7,6 +7,7 @@ use crossterm::{
 };
 use std::{
     io::{self, Stdout, Write},
+    panic,
     time::{Duration, Instant},
 };
 use tokio::task::yield_now;
@@ -15,26 +16,24 @@ use tui::{
     Terminal,
 };
 
-use crate::app::{App, NoopEngine};
+use crate::app::App;
 use crate::cli::CLIArgs;
-use async_uci::engine::{ChessEngine, Engine};
 
 mod app;
 mod board;
 mod cli;
 mod console;
+mod engine;
 mod fen;
+mod game;
 mod help;
+mod options;
 mod piece;
+mod pv;
+mod theme;
 mod tree;
 mod ui;
 
-async fn get_engine(path: String) -> Result<Engine> {
-    let mut eng = Engine::new(path.as_str()).await?;
-    eng.start_uci().await?;
-    Ok(eng)
-}
-
 fn init_terminal() -> Result<Terminal<CrosstermBackend<Stdout>>> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -44,6 +43,16 @@ fn init_terminal() -> Result<Terminal<CrosstermBackend<Stdout>>> {
     Ok(terminal)
 }
 
+/// Restore the terminal before printing the panic message, otherwise it's left in raw mode.
+fn set_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = disable_raw_mode();
+        let _ = execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture);
+        default_hook(info);
+    }));
+}
+
 fn close_terminal(term: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
     disable_raw_mode()?;
     execute!(
@@ -60,24 +69,22 @@ async fn main() -> Result<()> {
     let args = CLIArgs::parse();
     let tick_rate = Duration::from_millis(args.tickrate);
 
-    let app = match args.engine_path {
-        Some(path) => {
-            let engine = get_engine(path).await?;
-            let leaked_engine = Box::leak(Box::new(engine));
-            let app = App::new(leaked_engine).unwrap();
-            app
+    let mut app = App::new()?;
+    app.engine_autorestart = args.engine_autorestart;
+    app.log_evaluations = args.log_evals;
+    for path in args.engine_path {
+        // an engine failing to start leaves its slot empty, to be loaded again from the app
+        if let Err(err) = app.add_engine(path).await {
+            let ix = app.engine.slots.len();
+            app.report_error(format!("engine {}: {}", ix, err));
         }
-        None => {
-            let engine = NoopEngine {};
-            let leaked_engine = Box::leak(Box::new(engine));
-            let app = App::new(leaked_engine).unwrap();
-            app
-        }
-    };
+    }
 
+    set_panic_hook();
     let mut terminal = init_terminal()?;
-    let res = run_app(&mut terminal, app, tick_rate).await;
+    let res = run_app(&mut terminal, &mut app, tick_rate).await;
     close_terminal(&mut terminal)?;
+    app.engine.shutdown().await?;
 
     if let Err(err) = res {
         println!("ERR: {:?}", err);
@@ -88,13 +95,13 @@ async fn main() -> Result<()> {
 
 async fn run_app<B: Backend + Write>(
     terminal: &mut Terminal<B>,
-    mut app: App<'_>,
+    app: &mut App<'_>,
     tick_rate: Duration,
 ) -> Result<()> {
     let mut last_tick = Instant::now();
     let mut mouse_captured = true;
     loop {
-        terminal.draw(|f| ui::draw(f, &mut app))?;
+        terminal.draw(|f| ui::draw(f, app))?;
 
         let timeout = tick_rate
             .checked_sub(last_tick.elapsed())
@@ -110,8 +117,8 @@ async fn run_app<B: Backend + Write>(
                     KeyCode::Enter => app.on_enter().await,
                     KeyCode::Backspace => app.on_backspace(),
                     KeyCode::Delete => app.on_delete(),
-                    KeyCode::Left => app.on_left(),
-                    KeyCode::Right => app.on_right(),
+                    KeyCode::Left => app.on_left().await,
+                    KeyCode::Right => app.on_right().await,
                     KeyCode::Up => app.on_up(),
                     KeyCode::Down => app.on_down(),
                     KeyCode::F(2) => {
@@ -126,9 +133,11 @@ async fn run_app<B: Backend + Write>(
                     _ => {}
                 },
                 Event::Mouse(event) => match event.kind {
-                    MouseEventKind::Up(_) | MouseEventKind::Down(_) => app.on_mouse(event).await,
-                    MouseEventKind::ScrollDown => app.on_down(),
-                    MouseEventKind::ScrollUp => app.on_up(),
+                    MouseEventKind::Up(_) | MouseEventKind::Down(_) | MouseEventKind::Drag(_) => {
+                        app.on_mouse(event).await
+                    }
+                    MouseEventKind::ScrollDown => app.on_scroll(false),
+                    MouseEventKind::ScrollUp => app.on_scroll(true),
                     _ => {}
                 },
                 _ => {}
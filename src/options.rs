@@ -0,0 +1,366 @@
+use anyhow::Result;
+use async_uci::{engine::EngineOption, parse::OptionType};
+use thiserror::Error;
+use tui::widgets::TableState;
+
+#[derive(Debug, Clone, Error)]
+pub enum OptionError {
+    #[error("unknown engine option: {name}")]
+    UnknownOption { name: String },
+
+    #[error("invalid value for option {name}: {value}")]
+    InvalidValue { name: String, value: String },
+
+    #[error("value for option {name} out of bounds: {value} (min: {min}, max: {max})")]
+    OutOfBounds {
+        name: String,
+        value: isize,
+        min: isize,
+        max: isize,
+    },
+}
+
+/// An option advertised by the engine together with the value currently set.
+#[derive(Debug, Clone)]
+pub struct OptionEntry {
+    pub option: EngineOption,
+    pub value: String,
+}
+
+impl OptionEntry {
+    pub fn new(option: EngineOption) -> Self {
+        let value = default_value(&option.opt_type);
+        Self { option, value }
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self.option.opt_type {
+            OptionType::Check { .. } => "check",
+            OptionType::Spin { .. } => "spin",
+            OptionType::Combo { .. } => "combo",
+            OptionType::Button => "button",
+            OptionType::String { .. } => "string",
+        }
+    }
+
+    pub fn default(&self) -> String {
+        default_value(&self.option.opt_type)
+    }
+
+    /// Human readable bounds of the option, the range for spins and the variants for combos.
+    pub fn bounds(&self) -> String {
+        match &self.option.opt_type {
+            OptionType::Spin { min, max, .. } => format!("{} - {}", min, max),
+            OptionType::Combo { options, .. } => options.join(" | "),
+            OptionType::Check { .. } => "true | false".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    /// Check that the value is valid for the option and return it normalized.
+    pub fn validate(&self, value: &str) -> Result<String> {
+        let name = self.option.name.clone();
+        let value = value.trim();
+        match &self.option.opt_type {
+            OptionType::Check { .. } => match value.to_lowercase().as_str() {
+                "true" | "on" => Ok("true".to_string()),
+                "false" | "off" => Ok("false".to_string()),
+                _ => Err(OptionError::InvalidValue {
+                    name,
+                    value: value.to_string(),
+                }
+                .into()),
+            },
+            OptionType::Spin { min, max, .. } => {
+                let parsed = value
+                    .parse::<isize>()
+                    .map_err(|_| OptionError::InvalidValue {
+                        name: name.clone(),
+                        value: value.to_string(),
+                    })?;
+                if parsed < *min || parsed > *max {
+                    return Err(OptionError::OutOfBounds {
+                        name,
+                        value: parsed,
+                        min: *min,
+                        max: *max,
+                    }
+                    .into());
+                }
+                Ok(parsed.to_string())
+            }
+            OptionType::Combo { options, .. } => {
+                match options.iter().find(|o| o.eq_ignore_ascii_case(value)) {
+                    Some(o) => Ok(o.clone()),
+                    None => Err(OptionError::InvalidValue {
+                        name,
+                        value: value.to_string(),
+                    }
+                    .into()),
+                }
+            }
+            OptionType::Button => Ok(String::new()),
+            OptionType::String { .. } => Ok(value.to_string()),
+        }
+    }
+
+    /// Value obtained by stepping the current one. Checks are toggled, spins are
+    /// incremented or decremented by one and combos cycle through their variants.
+    /// Returns `None` for options that can't be stepped.
+    pub fn step(&self, forwards: bool) -> Option<String> {
+        match &self.option.opt_type {
+            OptionType::Check { .. } => Some((self.value != "true").to_string()),
+            OptionType::Spin { min, max, .. } => {
+                let current = self.value.parse::<isize>().unwrap_or(*min);
+                let next = match forwards {
+                    true => current.saturating_add(1).min(*max),
+                    false => current.saturating_sub(1).max(*min),
+                };
+                Some(next.to_string())
+            }
+            OptionType::Combo { options, .. } if !options.is_empty() => {
+                let current = options.iter().position(|o| *o == self.value).unwrap_or(0);
+                let next = match forwards {
+                    true => (current + 1) % options.len(),
+                    false => (current + options.len() - 1) % options.len(),
+                };
+                Some(options[next].clone())
+            }
+            _ => None,
+        }
+    }
+}
+
+fn default_value(opt_type: &OptionType) -> String {
+    match opt_type {
+        OptionType::Check { default } => default.to_string(),
+        OptionType::Spin { default, .. } => default.to_string(),
+        OptionType::Combo { default, .. } => default.clone(),
+        OptionType::Button => String::new(),
+        OptionType::String { default } => default.clone(),
+    }
+}
+
+/// Keeps the state of the Engine tab, where the engine options are listed and edited.
+pub struct EngineOptions {
+    pub entries: Vec<OptionEntry>,
+    pub state: TableState,
+    pub editing: Option<String>,
+}
+
+impl EngineOptions {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            state: TableState::default(),
+            editing: None,
+        }
+    }
+
+    /// Replace the listed options, keeping the values of the options already known.
+    pub fn set_options(&mut self, options: Vec<EngineOption>) {
+        let entries = options
+            .into_iter()
+            .map(|opt| match self.get(&opt.name) {
+                Some(prev) if prev.option == opt => prev.clone(),
+                _ => OptionEntry::new(opt),
+            })
+            .collect::<Vec<_>>();
+        self.entries = entries;
+        self.editing = None;
+        match self.entries.is_empty() {
+            true => self.state.select(None),
+            false => self.state.select(Some(
+                self.state
+                    .selected()
+                    .unwrap_or(0)
+                    .min(self.entries.len() - 1),
+            )),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&OptionEntry> {
+        self.entries
+            .iter()
+            .find(|e| e.option.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Record the value of an option after it was sent to the engine.
+    pub fn set_value(&mut self, name: &str, value: String) {
+        if let Some(entry) = self
+            .entries
+            .iter_mut()
+            .find(|e| e.option.name.eq_ignore_ascii_case(name))
+        {
+            entry.value = value;
+        }
+    }
+
+    /// Split the arguments of `:setoption` into the option name and its value. Option
+    /// names may contain spaces, so the longest advertised name the arguments start with
+    /// is taken, falling back to the first word when none matches.
+    pub fn split_assignment(&self, args: &str) -> (String, String) {
+        let args = args.trim();
+        let advertised = self
+            .entries
+            .iter()
+            .map(|e| e.option.name.as_str())
+            .filter(|name| {
+                args.get(..name.len())
+                    .is_some_and(|prefix| prefix.eq_ignore_ascii_case(name))
+                    && args[name.len()..]
+                        .chars()
+                        .next()
+                        .is_none_or(char::is_whitespace)
+            })
+            .max_by_key(|name| name.len());
+        let (name, value) = match advertised {
+            Some(name) => (name, &args[name.len()..]),
+            None => args.split_once(' ').unwrap_or((args, "")),
+        };
+        (name.to_string(), value.trim().to_string())
+    }
+
+    /// Validate a value for the given option. Options not advertised by the engine are
+    /// rejected, unless the engine didn't advertise any option at all.
+    pub fn validate(&self, name: &str, value: &str) -> Result<(String, String)> {
+        match self.get(name) {
+            Some(entry) => Ok((entry.option.name.clone(), entry.validate(value)?)),
+            None if self.entries.is_empty() => Ok((name.to_string(), value.trim().to_string())),
+            None => Err(OptionError::UnknownOption {
+                name: name.to_string(),
+            }
+            .into()),
+        }
+    }
+
+    pub fn selected(&self) -> Option<&OptionEntry> {
+        self.state.selected().and_then(|ix| self.entries.get(ix))
+    }
+
+    pub fn up(&mut self) {
+        if let Some(ix) = self.state.selected() {
+            self.state.select(Some(ix.saturating_sub(1)));
+        }
+    }
+
+    pub fn down(&mut self) {
+        if let Some(ix) = self.state.selected() {
+            self.state
+                .select(Some((ix + 1).min(self.entries.len().saturating_sub(1))));
+        }
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.editing.is_some()
+    }
+
+    pub fn start_editing(&mut self) {
+        if let Some(entry) = self.selected() {
+            self.editing = Some(entry.value.clone());
+        }
+    }
+
+    pub fn cancel_editing(&mut self) {
+        self.editing = None;
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        if let Some(buf) = self.editing.as_mut() {
+            buf.push(c);
+        }
+    }
+
+    pub fn delete_char(&mut self) {
+        if let Some(buf) = self.editing.as_mut() {
+            buf.pop();
+        }
+    }
+
+    /// Stop editing and return the option name with the validated edited value.
+    pub fn finish_editing(&mut self) -> Option<Result<(String, String)>> {
+        let value = self.editing.take()?;
+        let entry = self.selected()?;
+        Some(
+            entry
+                .validate(&value)
+                .map(|v| (entry.option.name.clone(), v)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use async_uci::{engine::EngineOption, parse::OptionType};
+
+    use crate::options::{EngineOptions, OptionEntry};
+
+    fn spin() -> OptionEntry {
+        OptionEntry::new(EngineOption {
+            name: "Hash".to_string(),
+            opt_type: OptionType::Spin {
+                default: 16,
+                min: 1,
+                max: 1024,
+            },
+        })
+    }
+
+    #[test]
+    fn test_validate() {
+        let hash = spin();
+        assert_eq!(hash.validate(" 64 ").unwrap(), "64");
+        assert!(hash.validate("0").is_err());
+        assert!(hash.validate("abc").is_err());
+
+        let ponder = OptionEntry::new(EngineOption {
+            name: "Ponder".to_string(),
+            opt_type: OptionType::Check { default: false },
+        });
+        assert_eq!(ponder.validate("ON").unwrap(), "true");
+        assert!(ponder.validate("maybe").is_err());
+    }
+
+    #[test]
+    fn test_step() {
+        let mut hash = spin();
+        assert_eq!(hash.step(true).unwrap(), "17");
+        hash.value = "1".to_string();
+        assert_eq!(hash.step(false).unwrap(), "1");
+
+        let style = OptionEntry::new(EngineOption {
+            name: "Style".to_string(),
+            opt_type: OptionType::Combo {
+                default: "Normal".to_string(),
+                options: vec!["Solid".to_string(), "Normal".to_string()],
+            },
+        });
+        assert_eq!(style.step(true).unwrap(), "Solid");
+        assert_eq!(style.step(false).unwrap(), "Solid");
+    }
+
+    #[test]
+    fn test_split_assignment() {
+        let mut options = EngineOptions::new();
+        options.set_options(vec![
+            spin().option,
+            EngineOption {
+                name: "Skill".to_string(),
+                opt_type: OptionType::Button,
+            },
+            EngineOption {
+                name: "Skill Level".to_string(),
+                opt_type: OptionType::Spin {
+                    default: 20,
+                    min: 0,
+                    max: 20,
+                },
+            },
+        ]);
+        let split = |args| options.split_assignment(args);
+        assert_eq!(split("skill level 5"), ("Skill Level".into(), "5".into()));
+        assert_eq!(split("Skill"), ("Skill".into(), "".into()));
+        assert_eq!(split("Hash 64"), ("Hash".into(), "64".into()));
+        assert_eq!(split("Hashes 64"), ("Hashes".into(), "64".into()));
+    }
+}
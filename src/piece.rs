@@ -129,10 +129,80 @@ impl Piece {
         }
     }
 
+    /// Multi-line drawing of the piece for large squares, 4 columns wide and 3 rows tall or
+    /// 6 columns wide and 4 rows tall when `large`. Both colours share the drawing.
+    pub fn as_block_art(&self, large: bool) -> &'static [&'static str] {
+        match (*self, large) {
+            (Piece::BlackKing | Piece::WhiteKing, false) => &[" ╬╬ ", " ██ ", "▟██▙"],
+            (Piece::BlackQueen | Piece::WhiteQueen, false) => &["▚▞▚▞", " ██ ", "▟██▙"],
+            (Piece::BlackRook | Piece::WhiteRook, false) => &["▙▄▄▟", " ██ ", "▟██▙"],
+            (Piece::BlackBishop | Piece::WhiteBishop, false) => &[" ▗▖ ", " ▜▛ ", "▟██▙"],
+            (Piece::BlackKnight | Piece::WhiteKnight, false) => &["▗▟█ ", " ▐█ ", "▟██▙"],
+            (Piece::BlackPawn | Piece::WhitePawn, false) => &["    ", " ▄▄ ", "▟██▙"],
+            (Piece::BlackKing | Piece::WhiteKing, true) => {
+                &["  ╬╬  ", " ▐██▌ ", "  ██  ", "▟████▙"]
+            }
+            (Piece::BlackQueen | Piece::WhiteQueen, true) => {
+                &[" ▚▞▚▞ ", " ▐██▌ ", "  ██  ", "▟████▙"]
+            }
+            (Piece::BlackRook | Piece::WhiteRook, true) => {
+                &[" ▙▄▄▟ ", "  ██  ", "  ██  ", "▟████▙"]
+            }
+            (Piece::BlackBishop | Piece::WhiteBishop, true) => {
+                &["  ▗▖  ", "  ▜▛  ", "  ██  ", "▟████▙"]
+            }
+            (Piece::BlackKnight | Piece::WhiteKnight, true) => {
+                &[" ▗▟██ ", " ▀▀██ ", "  ██  ", "▟████▙"]
+            }
+            (Piece::BlackPawn | Piece::WhitePawn, true) => {
+                &["      ", "  ▄▄  ", "  ██  ", "▟████▙"]
+            }
+        }
+    }
+
+    /// Multi-line drawing of the piece in plain ASCII, with the same sizes as `as_block_art`.
+    pub fn as_ascii_art(&self, large: bool) -> &'static [&'static str] {
+        match (*self, large) {
+            (Piece::BlackKing | Piece::WhiteKing, false) => &[" ++ ", " )( ", "/__\\"],
+            (Piece::BlackQueen | Piece::WhiteQueen, false) => &["\\/\\/", " )( ", "/__\\"],
+            (Piece::BlackRook | Piece::WhiteRook, false) => &["[__]", " || ", "/__\\"],
+            (Piece::BlackBishop | Piece::WhiteBishop, false) => &[" () ", " )( ", "/__\\"],
+            (Piece::BlackKnight | Piece::WhiteKnight, false) => &[" __ ", "/o )", "/__\\"],
+            (Piece::BlackPawn | Piece::WhitePawn, false) => &["    ", " () ", "/__\\"],
+            (Piece::BlackKing | Piece::WhiteKing, true) => {
+                &["  ++  ", "  )(  ", "  ||  ", " /__\\ "]
+            }
+            (Piece::BlackQueen | Piece::WhiteQueen, true) => {
+                &[" \\/\\/ ", "  )(  ", "  ||  ", " /__\\ "]
+            }
+            (Piece::BlackRook | Piece::WhiteRook, true) => {
+                &[" [__] ", "  ||  ", "  ||  ", " /__\\ "]
+            }
+            (Piece::BlackBishop | Piece::WhiteBishop, true) => {
+                &["  ()  ", "  )(  ", "  ||  ", " /__\\ "]
+            }
+            (Piece::BlackKnight | Piece::WhiteKnight, true) => {
+                &["  __  ", " /o ) ", "  /|  ", " /__\\ "]
+            }
+            (Piece::BlackPawn | Piece::WhitePawn, true) => {
+                &["      ", "  ()  ", "  )(  ", " /__\\ "]
+            }
+        }
+    }
+
     fn as_unicode_char(self) -> char {
         std::char::from_u32(self.as_unicode()).unwrap_or('�')
     }
 
+    /// The filled glyph of the piece, whatever its colour.
+    pub fn as_filled_unicode_char(self) -> char {
+        let offset = match self.is_white() {
+            true => 6,
+            false => 0,
+        };
+        std::char::from_u32(self.as_unicode() + offset).unwrap_or('�')
+    }
+
     pub fn get_moves(
         &self,
         board: &[u8; 64],
@@ -160,6 +230,53 @@ impl Piece {
         }
     }
 
+    /// Squares attacked by the piece, including the ones holding pieces of its own colour,
+    /// which it defends. Pawns only attack diagonally.
+    pub fn get_attacks(&self, board: &[u8; 64], position: u8) -> Vec<u8> {
+        let (directions, sliding): (&[i8], bool) = match *self {
+            Piece::BlackKing | Piece::WhiteKing => (&[8, -8, 1, -1, 7, -7, 9, -9], false),
+            Piece::BlackQueen | Piece::WhiteQueen => (&[8, -8, 1, -1, 7, -7, 9, -9], true),
+            Piece::BlackRook | Piece::WhiteRook => (&[8, -8, 1, -1], true),
+            Piece::BlackBishop | Piece::WhiteBishop => (&[7, -7, 9, -9], true),
+            Piece::BlackKnight | Piece::WhiteKnight => (&[6, -6, 10, -10, 15, -15, 17, -17], false),
+            Piece::BlackPawn => (&[7, 9], false),
+            Piece::WhitePawn => (&[-7, -9], false),
+        };
+        let mut squares = Vec::new();
+        for direction in directions {
+            let mut pos = position as i8;
+            loop {
+                let last_rank = pos % 8;
+                pos += direction;
+                // check bounds
+                if !(0..=63).contains(&pos) {
+                    break;
+                }
+                // a single step never changes the rank by more than 2, unless it wrapped
+                if (pos % 8 - last_rank).abs() > 2 {
+                    break;
+                }
+                squares.push(pos as u8);
+                if !sliding || board[pos as usize] != 0 {
+                    break;
+                }
+            }
+        }
+        squares
+    }
+
+    /// Material value in pawns, the king is worth more than everything else.
+    pub fn value(&self) -> u8 {
+        match *self {
+            Piece::BlackKing | Piece::WhiteKing => 100,
+            Piece::BlackQueen | Piece::WhiteQueen => 9,
+            Piece::BlackRook | Piece::WhiteRook => 5,
+            Piece::BlackBishop | Piece::WhiteBishop => 3,
+            Piece::BlackKnight | Piece::WhiteKnight => 3,
+            Piece::BlackPawn | Piece::WhitePawn => 1,
+        }
+    }
+
     fn get_castling_moves(
         &self,
         board: &[u8; 64],
@@ -359,10 +476,7 @@ impl Piece {
         last_move: Option<Move>,
     ) -> Option<Move> {
         // check there is a last move
-        let last_move = match last_move {
-            Some(m) => m,
-            None => return None,
-        };
+        let last_move = last_move?;
         // check last move was a pawn
         match Piece::try_from(board[last_move.to.as_ix() as usize]) {
             Ok(p) => {
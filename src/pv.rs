@@ -0,0 +1,131 @@
+use anyhow::Result;
+use thiserror::Error;
+
+use crate::board::{Board, Move};
+
+#[derive(Debug, Clone, Error)]
+pub enum PvError {
+    #[error("no principal variation to explore")]
+    NoLines,
+
+    #[error("the position changed since the line was taken, reopen the explorer")]
+    PositionChanged,
+}
+
+/// Principal variation reported by an engine.
+#[derive(Debug, Clone)]
+pub struct PvLine {
+    pub name: String,
+    /// Moves of the line in long algebraic notation, only the ones legal in the position.
+    pub moves: Vec<String>,
+}
+
+/// Steps through the principal variations of the engines on a preview board, leaving the
+/// game board untouched. The lines are a snapshot taken when the explorer is opened.
+pub struct PvExplorer {
+    start: Board,
+    pub lines: Vec<PvLine>,
+    pub selected: usize,
+    /// Moves of the selected line played on the preview board.
+    pub ply: usize,
+    pub board: Board,
+}
+
+impl PvExplorer {
+    pub fn new(fen: String, lines: Vec<PvLine>, flipped: bool) -> Result<Self> {
+        let mut board = Board::from_fen(fen)?;
+        board.set_flipped(flipped);
+        let lines = lines
+            .into_iter()
+            .map(|line| PvLine {
+                moves: legal_prefix(&board, &line.moves),
+                name: line.name,
+            })
+            .filter(|line| !line.moves.is_empty())
+            .collect::<Vec<_>>();
+        if lines.is_empty() {
+            return Err(PvError::NoLines.into());
+        }
+        Ok(Self {
+            start: board.clone(),
+            lines,
+            selected: 0,
+            ply: 0,
+            board,
+        })
+    }
+
+    /// FEN of the position the lines start from.
+    pub fn fen(&self) -> String {
+        self.start.as_fen()
+    }
+
+    pub fn set_flipped(&mut self, flipped: bool) {
+        self.start.set_flipped(flipped);
+        self.board.set_flipped(flipped);
+    }
+
+    pub fn current(&self) -> &PvLine {
+        &self.lines[self.selected]
+    }
+
+    pub fn forward(&mut self) {
+        self.set_ply(self.ply + 1);
+    }
+
+    pub fn back(&mut self) {
+        self.set_ply(self.ply.saturating_sub(1));
+    }
+
+    pub fn up(&mut self) {
+        self.select(self.selected.saturating_sub(1));
+    }
+
+    pub fn down(&mut self) {
+        self.select((self.selected + 1).min(self.lines.len() - 1));
+    }
+
+    /// Moves of the selected line up to the previewed position, ready to be played on the
+    /// game board.
+    pub fn played_moves(&self) -> Vec<Move> {
+        let mut board = self.start.clone();
+        let mut moves = Vec::with_capacity(self.ply);
+        for uci in self.current().moves.iter().take(self.ply) {
+            match board.find_legal_move(uci) {
+                Some(mov) if board.make_move(mov).is_ok() => moves.push(mov),
+                _ => break,
+            }
+        }
+        moves
+    }
+
+    fn select(&mut self, ix: usize) {
+        self.selected = ix;
+        self.set_ply(self.ply);
+    }
+
+    fn set_ply(&mut self, ply: usize) {
+        self.ply = ply.min(self.current().moves.len());
+        let mut board = self.start.clone();
+        for uci in self.current().moves.iter().take(self.ply) {
+            if let Some(mov) = board.find_legal_move(uci) {
+                let _ = board.make_move(mov);
+            }
+        }
+        self.board = board;
+    }
+}
+
+/// The moves of the line until the first one that isn't legal, engines may send a line
+/// computed for the previous position right after a move is made.
+fn legal_prefix(board: &Board, moves: &[String]) -> Vec<String> {
+    let mut board = board.clone();
+    let mut legal = Vec::new();
+    for uci in moves {
+        match board.find_legal_move(uci) {
+            Some(mov) if board.make_move(mov).is_ok() => legal.push(uci.clone()),
+            _ => break,
+        }
+    }
+    legal
+}
@@ -0,0 +1,522 @@
+use std::env;
+
+use anyhow::Result;
+use thiserror::Error;
+use tui::style::Color;
+
+#[derive(Debug, Clone, Error)]
+pub enum ThemeError {
+    #[error("unknown theme: {name}, available themes are {}", theme_names().join(", "))]
+    UnknownTheme { name: String },
+
+    #[error("unknown piece set: {name}, available piece sets are {}", piece_set_names().join(", "))]
+    UnknownPieceSet { name: String },
+}
+
+/// How the pieces are drawn. The sides are told apart by the colour of the pieces.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PieceSet {
+    /// FEN letters, uppercase for white and lowercase for black.
+    Ascii,
+    /// Outlined glyphs for white and filled ones for black, block art on large squares.
+    Unicode,
+    /// Filled glyphs for both sides, block art on large squares.
+    Filled,
+    /// ASCII art on large squares, letters on the smaller ones.
+    Art,
+}
+
+impl PieceSet {
+    pub const ALL: [PieceSet; 4] = [
+        PieceSet::Ascii,
+        PieceSet::Unicode,
+        PieceSet::Filled,
+        PieceSet::Art,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PieceSet::Ascii => "ascii",
+            PieceSet::Unicode => "unicode",
+            PieceSet::Filled => "filled",
+            PieceSet::Art => "art",
+        }
+    }
+
+    pub fn by_name(name: &str) -> Result<PieceSet> {
+        PieceSet::ALL
+            .into_iter()
+            .find(|set| set.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                ThemeError::UnknownPieceSet {
+                    name: name.to_string(),
+                }
+                .into()
+            })
+    }
+}
+
+pub fn piece_set_names() -> Vec<&'static str> {
+    PieceSet::ALL.iter().map(|set| set.name()).collect()
+}
+
+/// How many colours the terminal can show.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSupport {
+    /// The 16 named ANSI colours.
+    Basic,
+    /// The 256 colour palette.
+    Indexed,
+    /// 24-bit RGB colours.
+    TrueColor,
+}
+
+impl ColorSupport {
+    /// Guess the colour support from `COLORTERM` and `TERM`, the variables most terminals set.
+    pub fn detect() -> Self {
+        let colorterm = env::var("COLORTERM").unwrap_or_default();
+        let term = env::var("TERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            ColorSupport::TrueColor
+        } else if term.contains("256") {
+            ColorSupport::Indexed
+        } else {
+            ColorSupport::Basic
+        }
+    }
+}
+
+/// Colours used to draw the board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Palette {
+    pub light_square: Color,
+    pub dark_square: Color,
+    pub white_piece: Color,
+    pub black_piece: Color,
+    pub grabbed: Color,
+    /// Square under the keyboard cursor.
+    pub cursor: Color,
+    pub legal_move: Color,
+    pub last_move: Color,
+    pub check: Color,
+    pub best_move: Color,
+    /// The expected reply to the best move.
+    pub reply: Color,
+    pub threat: Color,
+    pub attacked_light: Color,
+    pub attacked_dark: Color,
+    pub en_prise: Color,
+    /// Squares controlled by white, from one attacker more than black to five or more.
+    pub control_white: [Color; 5],
+    pub control_black: [Color; 5],
+    /// Draw the highlighted squares in reverse video, for palettes without colours that
+    /// stand out from both the pieces and the squares. The highlight colour fills the square
+    /// and the piece is drawn over a cell of its own colour.
+    pub reversed_highlights: bool,
+}
+
+impl Palette {
+    fn map(self, f: fn(Color) -> Color) -> Self {
+        Palette {
+            light_square: f(self.light_square),
+            dark_square: f(self.dark_square),
+            white_piece: f(self.white_piece),
+            black_piece: f(self.black_piece),
+            grabbed: f(self.grabbed),
+            cursor: f(self.cursor),
+            legal_move: f(self.legal_move),
+            last_move: f(self.last_move),
+            check: f(self.check),
+            best_move: f(self.best_move),
+            reply: f(self.reply),
+            threat: f(self.threat),
+            attacked_light: f(self.attacked_light),
+            attacked_dark: f(self.attacked_dark),
+            en_prise: f(self.en_prise),
+            control_white: self.control_white.map(f),
+            control_black: self.control_black.map(f),
+            reversed_highlights: self.reversed_highlights,
+        }
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        THEMES[0].palette(ColorSupport::Basic)
+    }
+}
+
+/// A named set of board colours. `colors` is used as is on truecolour terminals and
+/// reduced to the closest colours on 256 colour ones, `basic` is used on the rest.
+#[derive(Debug)]
+pub struct Theme {
+    pub name: &'static str,
+    colors: Palette,
+    basic: Palette,
+}
+
+impl Theme {
+    pub fn by_name(name: &str) -> Result<&'static Theme> {
+        THEMES
+            .iter()
+            .find(|t| t.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                ThemeError::UnknownTheme {
+                    name: name.to_string(),
+                }
+                .into()
+            })
+    }
+
+    pub fn palette(&self, support: ColorSupport) -> Palette {
+        match support {
+            ColorSupport::TrueColor => self.colors,
+            ColorSupport::Indexed => self.colors.map(to_indexed),
+            ColorSupport::Basic => self.basic,
+        }
+    }
+}
+
+pub fn theme_names() -> Vec<&'static str> {
+    THEMES.iter().map(|t| t.name).collect()
+}
+
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color::Rgb(r, g, b)
+}
+
+const BASIC_CONTROL_WHITE: [Color; 5] = [
+    Color::Blue,
+    Color::Blue,
+    Color::LightBlue,
+    Color::LightBlue,
+    Color::LightBlue,
+];
+const BASIC_CONTROL_BLACK: [Color; 5] = [
+    Color::Red,
+    Color::Red,
+    Color::LightRed,
+    Color::LightRed,
+    Color::LightRed,
+];
+const CONTROL_WHITE: [Color; 5] = [
+    rgb(0, 0, 95),
+    rgb(0, 0, 135),
+    rgb(0, 0, 175),
+    rgb(0, 0, 215),
+    rgb(0, 0, 255),
+];
+const CONTROL_BLACK: [Color; 5] = [
+    rgb(95, 0, 0),
+    rgb(135, 0, 0),
+    rgb(175, 0, 0),
+    rgb(215, 0, 0),
+    rgb(255, 0, 0),
+];
+
+/// The original colours of the board, in named colours so they follow the terminal's palette.
+const CLASSIC: Palette = Palette {
+    light_square: Color::Gray,
+    dark_square: Color::DarkGray,
+    white_piece: Color::White,
+    black_piece: Color::Black,
+    grabbed: Color::LightRed,
+    cursor: Color::Cyan,
+    legal_move: Color::LightGreen,
+    last_move: Color::LightCyan,
+    check: Color::Red,
+    best_move: Color::LightBlue,
+    reply: Color::LightMagenta,
+    threat: Color::Red,
+    attacked_light: Color::LightYellow,
+    attacked_dark: Color::Yellow,
+    en_prise: Color::Red,
+    control_white: CONTROL_WHITE,
+    control_black: CONTROL_BLACK,
+    reversed_highlights: false,
+};
+
+pub const THEMES: [Theme; 5] = [
+    Theme {
+        name: "classic",
+        colors: CLASSIC,
+        basic: Palette {
+            control_white: BASIC_CONTROL_WHITE,
+            control_black: BASIC_CONTROL_BLACK,
+            ..CLASSIC
+        },
+    },
+    Theme {
+        name: "green",
+        colors: Palette {
+            light_square: rgb(205, 210, 170),
+            dark_square: rgb(118, 150, 86),
+            white_piece: rgb(255, 255, 255),
+            black_piece: rgb(30, 30, 30),
+            grabbed: rgb(246, 246, 105),
+            cursor: rgb(230, 120, 40),
+            legal_move: rgb(186, 202, 68),
+            last_move: rgb(205, 210, 106),
+            check: rgb(235, 97, 80),
+            best_move: rgb(105, 160, 215),
+            reply: rgb(180, 120, 200),
+            threat: rgb(215, 60, 50),
+            attacked_light: rgb(250, 215, 110),
+            attacked_dark: rgb(215, 170, 60),
+            en_prise: rgb(200, 20, 20),
+            control_white: CONTROL_WHITE,
+            control_black: CONTROL_BLACK,
+            reversed_highlights: false,
+        },
+        basic: Palette {
+            light_square: Color::Gray,
+            dark_square: Color::Green,
+            legal_move: Color::LightGreen,
+            last_move: Color::LightYellow,
+            control_white: BASIC_CONTROL_WHITE,
+            control_black: BASIC_CONTROL_BLACK,
+            ..CLASSIC
+        },
+    },
+    Theme {
+        name: "blue",
+        colors: Palette {
+            light_square: rgb(190, 200, 210),
+            dark_square: rgb(140, 162, 173),
+            white_piece: rgb(255, 255, 255),
+            black_piece: rgb(20, 30, 40),
+            grabbed: rgb(240, 130, 110),
+            cursor: rgb(250, 200, 60),
+            legal_move: rgb(110, 210, 110),
+            last_move: rgb(120, 190, 250),
+            check: rgb(230, 70, 70),
+            best_move: rgb(80, 120, 220),
+            reply: rgb(190, 120, 210),
+            threat: rgb(210, 50, 50),
+            attacked_light: rgb(250, 225, 150),
+            attacked_dark: rgb(210, 175, 80),
+            en_prise: rgb(200, 20, 20),
+            control_white: CONTROL_WHITE,
+            control_black: CONTROL_BLACK,
+            reversed_highlights: false,
+        },
+        basic: Palette {
+            light_square: Color::Gray,
+            dark_square: Color::Blue,
+            last_move: Color::LightCyan,
+            best_move: Color::Cyan,
+            cursor: Color::Magenta,
+            // the blue of the other themes would vanish on the dark squares
+            control_white: [
+                Color::Cyan,
+                Color::Cyan,
+                Color::LightBlue,
+                Color::LightBlue,
+                Color::LightBlue,
+            ],
+            control_black: BASIC_CONTROL_BLACK,
+            ..CLASSIC
+        },
+    },
+    Theme {
+        name: "high-contrast",
+        colors: Palette {
+            light_square: rgb(255, 255, 255),
+            dark_square: rgb(0, 0, 0),
+            white_piece: rgb(255, 215, 0),
+            black_piece: rgb(0, 160, 255),
+            grabbed: rgb(255, 0, 0),
+            cursor: rgb(160, 0, 255),
+            legal_move: rgb(0, 255, 0),
+            last_move: rgb(0, 255, 255),
+            check: rgb(255, 0, 255),
+            best_move: rgb(0, 95, 255),
+            reply: rgb(255, 135, 0),
+            threat: rgb(255, 0, 0),
+            attacked_light: rgb(255, 255, 0),
+            attacked_dark: rgb(175, 175, 0),
+            en_prise: rgb(255, 0, 0),
+            control_white: CONTROL_WHITE,
+            control_black: CONTROL_BLACK,
+            reversed_highlights: false,
+        },
+        basic: Palette {
+            light_square: Color::White,
+            dark_square: Color::Black,
+            white_piece: Color::LightYellow,
+            black_piece: Color::LightCyan,
+            grabbed: Color::Red,
+            cursor: Color::LightBlue,
+            legal_move: Color::Green,
+            last_move: Color::Cyan,
+            check: Color::Magenta,
+            best_move: Color::Blue,
+            reply: Color::Yellow,
+            threat: Color::Red,
+            attacked_light: Color::LightYellow,
+            attacked_dark: Color::Yellow,
+            en_prise: Color::LightRed,
+            control_white: BASIC_CONTROL_WHITE,
+            control_black: BASIC_CONTROL_BLACK,
+            reversed_highlights: false,
+        },
+    },
+    Theme {
+        name: "monochrome",
+        // no gray stands out from both the pieces and the squares, so the highlights are
+        // reversed, filling the squares with white or black
+        colors: Palette {
+            light_square: rgb(188, 188, 188),
+            dark_square: rgb(108, 108, 108),
+            white_piece: rgb(255, 255, 255),
+            black_piece: rgb(0, 0, 0),
+            grabbed: rgb(246, 246, 246),
+            cursor: rgb(255, 255, 255),
+            legal_move: rgb(236, 236, 236),
+            last_move: rgb(50, 50, 50),
+            check: rgb(0, 0, 0),
+            best_move: rgb(241, 241, 241),
+            reply: rgb(40, 40, 40),
+            threat: rgb(20, 20, 20),
+            attacked_light: rgb(236, 236, 236),
+            attacked_dark: rgb(60, 60, 60),
+            en_prise: rgb(10, 10, 10),
+            control_white: [
+                rgb(236, 236, 236),
+                rgb(241, 241, 241),
+                rgb(246, 246, 246),
+                rgb(251, 251, 251),
+                rgb(255, 255, 255),
+            ],
+            control_black: [
+                rgb(60, 60, 60),
+                rgb(45, 45, 45),
+                rgb(30, 30, 30),
+                rgb(15, 15, 15),
+                rgb(0, 0, 0),
+            ],
+            reversed_highlights: true,
+        },
+        basic: Palette {
+            light_square: Color::Gray,
+            dark_square: Color::DarkGray,
+            white_piece: Color::White,
+            black_piece: Color::Black,
+            grabbed: Color::White,
+            cursor: Color::White,
+            legal_move: Color::White,
+            last_move: Color::Black,
+            check: Color::Black,
+            best_move: Color::White,
+            reply: Color::Black,
+            threat: Color::Black,
+            attacked_light: Color::White,
+            attacked_dark: Color::Black,
+            en_prise: Color::Black,
+            control_white: [Color::White; 5],
+            control_black: [Color::Black; 5],
+            reversed_highlights: true,
+        },
+    },
+];
+
+/// The closest colour of the 256 colour palette, either in its 6x6x6 colour cube or in its
+/// grayscale ramp. Named and indexed colours are kept.
+fn to_indexed(color: Color) -> Color {
+    let (r, g, b) = match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        c => return c,
+    };
+    const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let level = |v: u8| {
+        (0..CUBE.len())
+            .min_by_key(|&i| (CUBE[i] as i16 - v as i16).abs())
+            .unwrap_or(0)
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = (CUBE[ri], CUBE[gi], CUBE[bi]);
+    // grayscale ramp, 24 steps from 8 to 238
+    let avg = (r as u16 + g as u16 + b as u16) / 3;
+    let step = (avg.saturating_sub(3) / 10).min(23) as u8;
+    let gray = 8 + step * 10;
+    let dist = |(x, y, z): (u8, u8, u8)| {
+        [(x, r), (y, g), (z, b)]
+            .iter()
+            .map(|&(a, b)| (a as i32 - b as i32).pow(2))
+            .sum::<i32>()
+    };
+    match dist((gray, gray, gray)) < dist(cube) {
+        true => Color::Indexed(232 + step),
+        false => Color::Indexed(16 + 36 * ri as u8 + 6 * gi as u8 + bi as u8),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_to_indexed() {
+        assert_eq!(to_indexed(rgb(0, 0, 0)), Color::Indexed(16));
+        assert_eq!(to_indexed(rgb(255, 255, 255)), Color::Indexed(231));
+        assert_eq!(to_indexed(rgb(255, 0, 0)), Color::Indexed(196));
+        assert_eq!(to_indexed(rgb(128, 128, 128)), Color::Indexed(244));
+        assert_eq!(to_indexed(Color::Gray), Color::Gray);
+        assert!(Theme::by_name("Green").is_ok());
+        assert!(Theme::by_name("purple").is_err());
+        assert_eq!(PieceSet::by_name("ART").unwrap(), PieceSet::Art);
+        assert!(PieceSet::by_name("wooden").is_err());
+    }
+
+    /// Whether the colours are told apart, rgb colours need a channel differing enough.
+    fn distinct(a: Color, b: Color) -> bool {
+        match (a, b) {
+            (Color::Rgb(r1, g1, b1), Color::Rgb(r2, g2, b2)) => {
+                r1.abs_diff(r2).max(g1.abs_diff(g2)).max(b1.abs_diff(b2)) >= 48
+            }
+            _ => a != b,
+        }
+    }
+
+    #[test]
+    fn test_highlights_contrast_with_pieces() {
+        let palettes = THEMES.iter().flat_map(|t| {
+            [
+                ColorSupport::TrueColor,
+                ColorSupport::Indexed,
+                ColorSupport::Basic,
+            ]
+            .map(|s| t.palette(s))
+        });
+        for p in palettes {
+            let highlights = [p.check, p.best_move, p.reply, p.threat, p.en_prise];
+            let marks = [p.grabbed, p.cursor, p.legal_move, p.last_move];
+            for color in highlights
+                .iter()
+                .chain(&p.control_white)
+                .chain(&p.control_black)
+            {
+                // reversed highlights draw the pieces over a cell of their own colour
+                if !p.reversed_highlights {
+                    assert!(distinct(*color, p.white_piece), "{:?}", color);
+                    assert!(distinct(*color, p.black_piece), "{:?}", color);
+                }
+            }
+            let attacked = [p.attacked_light, p.attacked_dark];
+            for color in highlights
+                .iter()
+                .chain(&marks)
+                .chain(&attacked)
+                .chain(&p.control_white)
+                .chain(&p.control_black)
+            {
+                assert!(distinct(*color, p.light_square), "{:?}", color);
+                assert!(distinct(*color, p.dark_square), "{:?}", color);
+            }
+            for piece in [p.white_piece, p.black_piece] {
+                assert!(distinct(piece, p.light_square), "{:?}", piece);
+                assert!(distinct(piece, p.dark_square), "{:?}", piece);
+            }
+        }
+    }
+}
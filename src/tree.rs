@@ -1,4 +1,4 @@
-use tui_tree_widget::{TreeItem, TreeState};
+use tui_tree_widget::{flatten, TreeItem, TreeState};
 
 pub struct StatefulTree<'a> {
     pub state: TreeState,
@@ -39,7 +39,20 @@ impl<'a> StatefulTree<'a> {
         self.state.key_right();
     }
 
-    #[allow(dead_code)]
+    /// Select the item drawn at `row`, counting from the first visible row. Clicking the
+    /// selected item opens or closes it.
+    pub fn click(&mut self, row: usize) {
+        let visible = flatten(&self.state.get_all_opened(), &self.items);
+        let identifier = match visible.get(self.state.get_offset() + row) {
+            Some(item) => item.identifier.clone(),
+            None => return,
+        };
+        match identifier == self.state.selected() {
+            true => self.state.toggle_selected(),
+            false => self.state.select(identifier),
+        }
+    }
+
     pub fn toggle(&mut self) {
         self.state.toggle_selected();
     }
@@ -1,22 +1,50 @@
+use async_uci::engine::Evaluation;
 use tui::{
     backend::Backend,
+    buffer::Buffer,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
+    symbols::Marker,
     symbols::DOT,
     text::{Span, Spans},
-    widgets::{Block, Borders, Paragraph, Tabs, Wrap},
+    widgets::{
+        Axis, Block, Borders, Cell, Chart, Clear, Dataset, GraphType, Paragraph, Row, Table, Tabs,
+        Widget, Wrap,
+    },
     Frame,
 };
 use tui_tree_widget::Tree;
 
-use crate::app::{App, SecondaryBoardPane};
+use crate::{
+    app::{App, HitAreas, MoveGrouping, SecondaryBoardPane},
+    board::SquareSize,
+    engine::{SearchInfo, Wdl},
+};
+
+/// Pawns of advantage at the top and bottom of the evaluation graph.
+const EVAL_GRAPH_LIMIT: f64 = 10.0;
+
+/// Width of the popup listing the moves matching the typed move, including its borders.
+const MOVE_INPUT_WIDTH: u16 = 34;
+
+/// Width of a move in the popup, enough for the longest SAN, i.e. `exd8=Q+`.
+const MOVE_COLUMN_WIDTH: u16 = 8;
+
+/// Width of the evaluation bar beside the board, including its borders.
+const EVAL_BAR_WIDTH: u16 = 6;
+
+/// Narrowest game info pane beside the board, the board shrinks to leave room for it.
+const GAME_INFO_MIN_WIDTH: u16 = 30;
 
 pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
+    // only the widgets drawn in this frame handle clicks
+    app.hit_areas = HitAreas::default();
     let chunks = Layout::default()
         .constraints(
             [
                 Constraint::Length(3),
                 Constraint::Min(2 * 8 + 1),
+                Constraint::Length(1),
                 Constraint::Length(3),
             ]
             .as_ref(),
@@ -26,10 +54,50 @@ pub fn draw<B: Backend>(f: &mut Frame<B>, app: &mut App) {
     match app.tabs.index {
         0 => draw_board(f, app, chunks[1]),
         1 => draw_console_log(f, app, chunks[1]),
-        2 => draw_help(f, app, chunks[1]),
+        2 => draw_engine_options(f, app, chunks[1]),
+        3 => draw_help(f, app, chunks[1]),
         _ => {}
     }
-    draw_console(f, app, chunks[2]);
+    draw_status_bar(f, app, chunks[2]);
+    draw_console(f, app, chunks[3]);
+}
+
+pub fn draw_status_bar<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let mut spans = Vec::new();
+    for (ix, slot) in app.engine.slots.iter().enumerate() {
+        let (status, color) = match slot.is_running() {
+            true if !slot.enabled => ("disabled", Color::DarkGray),
+            true if app.searching => ("searching", Color::LightGreen),
+            true => ("idle", Color::White),
+            false if slot.path.is_some() => ("stopped", Color::LightRed),
+            false => ("not loaded", Color::DarkGray),
+        };
+        let selected = match ix == app.engine.selected() && app.engine.slots.len() > 1 {
+            true => "*",
+            false => "",
+        };
+        spans.push(Span::raw(format!(
+            " Engine {}{}: {} ",
+            ix + 1,
+            selected,
+            slot.name()
+        )));
+        spans.push(Span::styled(
+            format!("[{}]", status),
+            Style::default().fg(color),
+        ));
+    }
+    if app.engine_autorestart {
+        spans.push(Span::raw(" [autorestart]"));
+    }
+    if let Some(err) = &app.status_error {
+        spans.push(Span::raw(" | "));
+        spans.push(Span::styled(
+            format!("err: {}", err),
+            Style::default().fg(Color::LightRed),
+        ));
+    }
+    f.render_widget(Paragraph::new(Spans::from(spans)), area);
 }
 
 pub fn draw_menu<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
@@ -46,6 +114,21 @@ pub fn draw_menu<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
         .cloned()
         .map(|s| Spans::from(s.to_string()))
         .collect();
+    // the titles are laid out like the Tabs widget does: padded by a space on each side and
+    // separated by the divider
+    let inner = block.inner(area);
+    let mut x = inner.x;
+    app.hit_areas.tabs = app
+        .tabs
+        .titles
+        .iter()
+        .map(|title| {
+            let width = title.chars().count() as u16;
+            let rect = Rect::new(x + 1, inner.y, width, 1).intersection(inner);
+            x += width + 2 + DOT.chars().count() as u16;
+            rect
+        })
+        .collect();
     let tabs = Tabs::new(titles)
         .block(block)
         .style(Style::default().fg(Color::White))
@@ -56,16 +139,191 @@ pub fn draw_menu<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
 }
 
 pub fn draw_board<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let square = SquareSize::fit(
+        area.width
+            .saturating_sub(EVAL_BAR_WIDTH + GAME_INFO_MIN_WIDTH),
+        area.height,
+    );
+    let (board_width, board_height) = square.board_size();
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Length(34), Constraint::Min(10)].as_ref())
+        .constraints(
+            [
+                Constraint::Length(board_width),
+                Constraint::Length(EVAL_BAR_WIDTH),
+                Constraint::Min(10),
+            ]
+            .as_ref(),
+        )
         .split(area);
-    let board_chunk = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Length(18), Constraint::Min(10)].as_ref())
-        .split(chunks[0])[0];
-    f.render_widget(app.board.clone(), board_chunk);
-    draw_game_info(f, app, chunks[1])
+    let board_chunk = Rect {
+        height: board_height.min(chunks[0].height),
+        ..chunks[0]
+    };
+    app.hit_areas.board = Some(board_chunk);
+    let mut board = app.board.clone();
+    board.set_selected_move(app.selected_move().map(|mov| (mov.from, mov.to)));
+    f.render_widget(board, board_chunk);
+    draw_move_input(f, app, board_chunk);
+    draw_eval_bar(
+        f,
+        app,
+        Rect::new(
+            chunks[1].x,
+            board_chunk.y,
+            chunks[1].width,
+            board_chunk.height,
+        ),
+    );
+    draw_game_info(f, app, chunks[2])
+}
+
+/// Draw the move being typed over the bottom of the board, with the legal moves it matches
+/// in columns. The move Enter would play is shown reversed.
+pub fn draw_move_input<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let input = match app.move_input.as_ref() {
+        Some(input) => input,
+        None => return,
+    };
+    let width = area.width.min(MOVE_INPUT_WIDTH);
+    let per_row = (width.saturating_sub(2) / MOVE_COLUMN_WIDTH).max(1) as usize;
+    let chosen = input.chosen();
+    let mut rows: Vec<Spans> = input
+        .candidates
+        .chunks(per_row)
+        .map(|row| {
+            let spans = row
+                .iter()
+                .map(|(mov, san)| {
+                    let style = match Some(*mov) == chosen {
+                        true => Style::default().add_modifier(Modifier::REVERSED),
+                        false => Style::default(),
+                    };
+                    Span::styled(
+                        format!("{:<width$}", san, width = MOVE_COLUMN_WIDTH as usize),
+                        style,
+                    )
+                })
+                .collect::<Vec<_>>();
+            Spans::from(spans)
+        })
+        .collect();
+    if rows.is_empty() {
+        rows.push(Spans::from(Span::styled(
+            "no legal move matches",
+            Style::default().fg(Color::Red),
+        )));
+    }
+    let height = (rows.len() as u16 + 2).min(area.height);
+    let popup = Rect::new(area.x, area.bottom() - height, width, height);
+    let block = Block::default()
+        .title(format!("Move: {}", input.text))
+        .borders(Borders::ALL);
+    f.render_widget(Clear, popup);
+    f.render_widget(Paragraph::new(rows).block(block), popup);
+}
+
+/// Draw a vertical bar filled with white's share of the expected result according to the
+/// selected engine, with white at the bottom unless the board is flipped.
+pub fn draw_eval_bar<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let block = Block::default().borders(Borders::ALL);
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    if inner.area() == 0 {
+        return;
+    }
+    let slot = app.engine.current();
+    let white_to_move = app.board.white_to_move();
+    let (share, label) = match slot.wdl() {
+        Some(wdl) => {
+            let wdl = match white_to_move {
+                true => wdl,
+                false => wdl.flipped(),
+            };
+            let ev = &slot.evaluation;
+            let sign = match white_to_move {
+                true => 1,
+                false => -1,
+            };
+            (
+                wdl.expected_score(),
+                eval_bar_label(ev.score * sign, ev.mate * sign),
+            )
+        }
+        None => (0.5, String::new()),
+    };
+    f.render_widget(
+        EvalBar {
+            share,
+            label,
+            flipped: app.flipped_board,
+        },
+        inner,
+    );
+}
+
+/// Vertical bar with white's share of the expected result filled from white's end.
+struct EvalBar {
+    share: f64,
+    label: String,
+    flipped: bool,
+}
+
+impl Widget for EvalBar {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let height = area.height as usize;
+        // filled in half rows, using half blocks for the row where the colours meet
+        let halves = height * 2;
+        let white_halves = (self.share * halves as f64).round() as usize;
+        for row in 0..height {
+            let from_white = match self.flipped {
+                true => row,
+                false => height - 1 - row,
+            };
+            let filled = white_halves.saturating_sub(from_white * 2).min(2);
+            let (symbol, style) = match (filled, self.flipped) {
+                (2, _) => (" ", Style::default().bg(Color::White)),
+                (0, _) => (" ", Style::default().bg(Color::DarkGray)),
+                (_, false) => ("▄", Style::default().fg(Color::White).bg(Color::DarkGray)),
+                (_, true) => ("▀", Style::default().fg(Color::White).bg(Color::DarkGray)),
+            };
+            for x in area.left()..area.right() {
+                buf.get_mut(x, area.y + row as u16)
+                    .set_symbol(symbol)
+                    .set_style(style);
+            }
+        }
+        if self.label.is_empty() {
+            return;
+        }
+        // the label goes at the end of the side that is ahead
+        let white_ahead = self.share >= 0.5;
+        let y = match white_ahead != self.flipped {
+            true => area.bottom() - 1,
+            false => area.top(),
+        };
+        let style = match white_ahead {
+            true => Style::default().fg(Color::Black).bg(Color::White),
+            false => Style::default().fg(Color::White).bg(Color::DarkGray),
+        };
+        let label = self
+            .label
+            .chars()
+            .take(area.width as usize)
+            .collect::<String>();
+        let x = area.x + (area.width - label.chars().count() as u16) / 2;
+        buf.set_string(x, y, label, style.add_modifier(Modifier::BOLD));
+    }
+}
+
+/// Short score for the eval bar from white's point of view, i.e. `+0.5`, `-12` or `M3`.
+fn eval_bar_label(score: isize, mate: isize) -> String {
+    match mate {
+        0 if score.abs() < 1000 => format!("{:+.1}", score as f64 / 100.0),
+        0 => format!("{:+.0}", score as f64 / 100.0),
+        m if m > 0 => format!("M{}", m),
+        m => format!("-M{}", -m),
+    }
 }
 
 pub fn draw_game_info<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
@@ -86,25 +344,179 @@ pub fn draw_game_info<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
             draw_evaluation(f, app, chunks[0]);
             draw_history(f, app, chunks[1]);
         }
+        SecondaryBoardPane::PvExplorer => draw_pv_explorer(f, app, area),
+        SecondaryBoardPane::EvalGraph => {
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)].as_ref())
+                .split(area);
+            draw_evaluation(f, app, chunks[0]);
+            draw_eval_graph(f, app, chunks[1]);
+        }
         _ => draw_evaluation(f, app, area),
     }
 }
 
+/// Draw the evaluation of each engine attached, splitting the area between them.
 pub fn draw_evaluation<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let slots = app
+        .engine
+        .slots
+        .iter()
+        .enumerate()
+        .filter(|(_, s)| s.is_running() || app.engine.slots.len() == 1)
+        .collect::<Vec<_>>();
+    if slots.is_empty() {
+        let block = Block::default()
+            .title("Engine Evaluation")
+            .borders(Borders::ALL);
+        f.render_widget(Paragraph::new("No engine running").block(block), area);
+        return;
+    }
+    let constraints = slots
+        .iter()
+        .map(|_| Constraint::Ratio(1, slots.len() as u32))
+        .collect::<Vec<_>>();
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(area);
+    for ((ix, slot), chunk) in slots.into_iter().zip(chunks.iter()) {
+        let mut title = match app.engine.slots.len() {
+            1 => format!("Engine Evaluation - {}", slot.name()),
+            _ => format!("Engine {} - {}", ix + 1, slot.name()),
+        };
+        if !slot.enabled {
+            title.push_str(" (disabled)");
+        }
+        let block = Block::default().title(title).borders(Borders::ALL);
+        let width = chunk.width.saturating_sub(2).max(1) as usize;
+        let mut text = engine_info_lines(&slot.evaluation, &slot.info);
+        if let Some(wdl) = slot.wdl() {
+            // shown from white's point of view, the engine reports it for the side to move
+            let wdl = match app.board.white_to_move() {
+                true => wdl,
+                false => wdl.flipped(),
+            };
+            text.push(Spans::from(""));
+            text.push(wdl_line(wdl, slot.info.wdl.is_none()));
+            text.push(wdl_bar(wdl, width));
+        }
+        text.push(Spans::from(""));
+        text.extend(wrap_text(
+            format!("Best: {}", slot.evaluation.pv.join(", ")),
+            width,
+        ));
+        let paragraph = Paragraph::new(text).block(block).wrap(Wrap { trim: true });
+        f.render_widget(paragraph, *chunk);
+    }
+}
+
+/// Draw the preview board of the PV explorer next to the explored lines.
+pub fn draw_pv_explorer<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let explorer = match app.pv_explorer.as_ref() {
+        Some(explorer) => explorer,
+        None => return,
+    };
+    let (board_width, board_height) = SquareSize::fit(area.width / 2, area.height).board_size();
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Length(board_width), Constraint::Min(10)].as_ref())
+        .split(area);
+    let board_chunk = Rect {
+        height: board_height.min(chunks[0].height),
+        ..chunks[0]
+    };
+    let mut board = explorer.board.clone();
+    board.set_palette(app.palette());
+    board.set_piece_set(app.piece_set);
+    board.set_title(format!(
+        "Preview - {}/{}",
+        explorer.ply,
+        explorer.current().moves.len()
+    ));
+    f.render_widget(board, board_chunk);
+
+    let mut text = Vec::new();
+    for (ix, line) in explorer.lines.iter().enumerate() {
+        let selected = ix == explorer.selected;
+        let marker = match selected {
+            true => ">> ",
+            false => "   ",
+        };
+        text.push(Spans::from(Span::styled(
+            format!("{}{}", marker, line.name),
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+        let moves = line
+            .moves
+            .iter()
+            .enumerate()
+            .flat_map(|(ply, mov)| {
+                let style = match selected && ply + 1 == explorer.ply {
+                    true => Style::default()
+                        .fg(Color::Black)
+                        .bg(Color::LightGreen)
+                        .add_modifier(Modifier::BOLD),
+                    false => Style::default(),
+                };
+                [Span::styled(mov.clone(), style), Span::raw(" ")]
+            })
+            .collect::<Vec<_>>();
+        text.push(Spans::from(moves));
+        text.push(Spans::from(""));
+    }
     let block = Block::default()
-        .title("Engine Evaluation")
+        .title("Principal Variations")
         .borders(Borders::ALL);
-    let mut text = wrap_text(format!("{}", app.last_engine_eval), area.width as usize - 2);
-    text.push(Spans::from(""));
-    text.extend(
-        wrap_text(
-            format!("Best: {}", app.last_engine_eval.pv.join(", ")),
-            area.width as usize - 2,
-        )
-        .into_iter(),
-    );
     let paragraph = Paragraph::new(text).block(block).wrap(Wrap { trim: true });
-    f.render_widget(paragraph, area);
+    f.render_widget(paragraph, chunks[1]);
+}
+
+/// Draw the evaluation of each ply of the game from white's point of view, with a vertical
+/// line on the ply shown on the board.
+pub fn draw_eval_graph<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let block = Block::default()
+        .title(format!(
+            "Evaluation Graph (±{} pawns, click or h/l to move)",
+            EVAL_GRAPH_LIMIT
+        ))
+        .borders(Borders::ALL);
+    // the chart has no axis labels, so the points are plotted over the whole inner area
+    app.hit_areas.eval_graph = Some(block.inner(area));
+    let plies = app.game.len().max(1) as f64;
+    let evals = app
+        .game
+        .evals()
+        .iter()
+        .enumerate()
+        .filter_map(|(ply, ev)| ev.map(|ev| (ply as f64, ev.pawns(EVAL_GRAPH_LIMIT))))
+        .collect::<Vec<_>>();
+    let zero = [(0.0, 0.0), (plies, 0.0)];
+    let current = app.board.get_history().len() as f64;
+    let cursor = [(current, -EVAL_GRAPH_LIMIT), (current, EVAL_GRAPH_LIMIT)];
+    let datasets = vec![
+        Dataset::default()
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::DarkGray))
+            .data(&zero),
+        Dataset::default()
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Yellow))
+            .data(&cursor),
+        Dataset::default()
+            .marker(Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::LightGreen))
+            .data(&evals),
+    ];
+    let chart = Chart::new(datasets)
+        .block(block)
+        .x_axis(Axis::default().bounds([0.0, plies]))
+        .y_axis(Axis::default().bounds([-EVAL_GRAPH_LIMIT, EVAL_GRAPH_LIMIT]));
+    f.render_widget(chart, area);
 }
 
 pub fn draw_history<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
@@ -117,12 +529,18 @@ pub fn draw_history<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
                 .add_modifier(Modifier::BOLD),
         )
         .highlight_symbol(">> ");
+    app.hit_areas.history = Some(Block::default().borders(Borders::ALL).inner(area));
     f.render_stateful_widget(items, area, &mut app.history_tree.state);
 }
 
 pub fn draw_moves_tree<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let title = match app.move_grouping {
+        MoveGrouping::Piece => "Legal Moves - by piece",
+        MoveGrouping::Kind => "Legal Moves - checks, captures, quiet",
+        MoveGrouping::Off => "Legal Moves",
+    };
     let items = Tree::new(app.moves_tree.items.clone())
-        .block(Block::default().borders(Borders::ALL).title("Legal Moves"))
+        .block(Block::default().borders(Borders::ALL).title(title))
         .highlight_style(
             Style::default()
                 .fg(Color::Black)
@@ -130,9 +548,71 @@ pub fn draw_moves_tree<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect)
                 .add_modifier(Modifier::BOLD),
         )
         .highlight_symbol(">> ");
+    app.hit_areas.moves_tree = Some(Block::default().borders(Borders::ALL).inner(area));
     f.render_stateful_widget(items, area, &mut app.moves_tree.state);
 }
 
+pub fn draw_engine_options<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
+    let selected_engine = app.engine.selected();
+    let slot = app.engine.current_mut();
+    let title = match &slot.path {
+        Some(path) => format!(
+            "Engine Options - {}: {} ({})",
+            selected_engine + 1,
+            slot.name(),
+            path
+        ),
+        None => format!("Engine Options - {}", slot.name()),
+    };
+    let options = &mut slot.options;
+    let selected = options.state.selected();
+    let editing = options.editing.clone();
+    let rows = options
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(ix, entry)| {
+            let value = match &editing {
+                Some(buf) if selected == Some(ix) => Cell::from(format!("{}_", buf)).style(
+                    Style::default()
+                        .fg(Color::Black)
+                        .bg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                ),
+                _ => Cell::from(entry.value.clone()),
+            };
+            Row::new(vec![
+                Cell::from(entry.option.name.clone()),
+                Cell::from(entry.type_name()),
+                value,
+                Cell::from(entry.default()),
+                Cell::from(entry.bounds()),
+            ])
+        })
+        .collect::<Vec<_>>();
+    let header = Row::new(vec!["Name", "Type", "Value", "Default", "Bounds"])
+        .style(Style::default().fg(Color::Yellow))
+        .bottom_margin(1);
+    let table = Table::new(rows)
+        .header(header)
+        .block(Block::default().title(title).borders(Borders::ALL))
+        .highlight_style(
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::LightGreen)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol(">> ")
+        .widths(&[
+            Constraint::Percentage(25),
+            Constraint::Length(8),
+            Constraint::Percentage(25),
+            Constraint::Percentage(20),
+            Constraint::Percentage(30),
+        ]);
+    f.render_stateful_widget(table, area, &mut options.state);
+}
+
 pub fn draw_console_log<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
     f.render_widget(app.console.log.widget(), area);
 }
@@ -148,6 +628,106 @@ pub fn draw_help<B: Backend>(f: &mut Frame<B>, app: &mut App, area: Rect) {
     f.render_widget(app.help.widget(), area);
 }
 
+/// Lines of the engine info panel, with the search statistics of the engine.
+fn engine_info_lines(ev: &Evaluation, info: &SearchInfo) -> Vec<Spans<'static>> {
+    let searched = ev.depth > 0;
+    let score = match ev.mate {
+        _ if !searched => "-".to_string(),
+        0 => format!("{:+.2}", ev.score as f64 / 100.0),
+        m if m > 0 => format!("M{}", m),
+        m => format!("-M{}", -m),
+    };
+    let depth = match searched {
+        true => format!("{}/{}", ev.depth, ev.seldepth),
+        false => "-".to_string(),
+    };
+    let nodes = match searched {
+        true => format_count(ev.nodes.max(0) as usize),
+        false => "-".to_string(),
+    };
+    let currmove = match (&info.currmove, info.currmovenumber) {
+        (Some(mov), Some(n)) => format!("{} ({})", mov, n),
+        (Some(mov), None) => mov.clone(),
+        _ => "-".to_string(),
+    };
+    let elapsed = match info.elapsed() {
+        Some(elapsed) => format!("{:.1}s", elapsed.as_secs_f64()),
+        None => "-".to_string(),
+    };
+    vec![
+        info_line(("Score", score), ("Depth", depth)),
+        info_line(
+            ("Nodes", nodes),
+            ("NPS", info.nps.map_or("-".to_string(), format_count)),
+        ),
+        info_line(
+            (
+                "Hash",
+                info.hashfull
+                    .map_or("-".to_string(), |h| format!("{:.1}%", h as f64 / 10.0)),
+            ),
+            ("TB hits", info.tbhits.map_or("-".to_string(), format_count)),
+        ),
+        info_line(("Move", currmove), ("Time", elapsed)),
+    ]
+}
+
+/// Percentages of a white win, a draw and a black win.
+fn wdl_line(wdl: Wdl, estimated: bool) -> Spans<'static> {
+    let label = Style::default().fg(Color::DarkGray);
+    let percent = |p: usize| format!("{:.1}%  ", p as f64 / 10.0);
+    let mut spans = vec![
+        Span::styled("White: ", label),
+        Span::raw(percent(wdl.win)),
+        Span::styled("Draw: ", label),
+        Span::raw(percent(wdl.draw)),
+        Span::styled("Black: ", label),
+        Span::raw(percent(wdl.loss)),
+    ];
+    if estimated {
+        spans.push(Span::styled("(estimated)", label));
+    }
+    Spans::from(spans)
+}
+
+/// Stacked bar with the white win, draw and black win probabilities, using different
+/// shades so it can be read without colours.
+fn wdl_bar(wdl: Wdl, width: usize) -> Spans<'static> {
+    // engines are expected to report per mille values, but nothing holds them to it
+    let total = (wdl.win as f64 + wdl.draw as f64 + wdl.loss as f64).max(1.0);
+    let share = |n: usize| ((n as f64 / total * width as f64).round() as usize).min(width);
+    let win = share(wdl.win);
+    let loss = share(wdl.loss).min(width - win);
+    let draw = width - win - loss;
+    Spans::from(vec![
+        Span::styled("█".repeat(win), Style::default().fg(Color::White)),
+        Span::styled("▒".repeat(draw), Style::default().fg(Color::Gray)),
+        Span::styled("░".repeat(loss), Style::default().fg(Color::DarkGray)),
+    ])
+}
+
+/// A line with two labelled values, the second one aligned in a column.
+fn info_line(left: (&str, String), right: (&str, String)) -> Spans<'static> {
+    let label = Style::default().fg(Color::DarkGray);
+    Spans::from(vec![
+        Span::styled(format!("{}: ", left.0), label),
+        // pad the first column to the same width whatever the label length
+        Span::raw(format!("{:<1$}", left.1, 18 - left.0.len().min(16))),
+        Span::styled(format!("{}: ", right.0), label),
+        Span::raw(right.1),
+    ])
+}
+
+/// Format big counts with a metric suffix, i.e. 1234567 as 1.23M
+fn format_count(n: usize) -> String {
+    match n {
+        n if n >= 1_000_000_000 => format!("{:.2}G", n as f64 / 1e9),
+        n if n >= 1_000_000 => format!("{:.2}M", n as f64 / 1e6),
+        n if n >= 10_000 => format!("{:.1}k", n as f64 / 1e3),
+        n => n.to_string(),
+    }
+}
+
 fn wrap_text(text: String, width: usize) -> Vec<Spans<'static>> {
     text.chars()
         .collect::<Vec<_>>()